@@ -17,34 +17,54 @@ pub fn generate_ast(output_dir: &str) -> io::Result<()> {
         &[
             "Assign   : Token name, Rc<Expr> value",
             "Binary   : Rc<Expr> left, Token operator, Rc<Expr> right",
-            "Call     : Rc<Expr> callee, Token paren, Vec<Rc<Expr>> arguments",
-            "Get      : Rc<Expr> object, Token name",
+            "Call     : Rc<Expr> callee, Token paren, Vec<Rc<Expr>> arguments, Vec<Option<Token>> names",
+            "Compound : Rc<Expr> target, Token operator, Rc<Expr> value",
+            "Conditional : Rc<Expr> condition, Rc<Expr> then_branch, Rc<Expr> else_branch",
+            "Get      : Rc<Expr> object, Token name, bool optional",
             "Grouping : Rc<Expr> expression",
+            "Index    : Rc<Expr> object, Token bracket, Rc<Expr> index",
+            "Interpolation : Vec<Rc<Expr>> parts",
+            "List     : Vec<Rc<Expr>> elements",
             "Literal  : Option<Object> value",
             "Logical  : Rc<Expr> left, Token operator, Rc<Expr> right",
+            "Map      : Token brace, Vec<Rc<Expr>> keys, Vec<Rc<Expr>> values",
+            "OptionalChain : Rc<Expr> expression",
             "Set      : Rc<Expr> object, Token name, Rc<Expr> value",
+            "Slice    : Rc<Expr> object, Token bracket, Option<Rc<Expr>> start, Option<Rc<Expr>> end",
+            "SetIndex : Rc<Expr> object, Token bracket, Rc<Expr> index, Rc<Expr> value",
             "Super    : Token keyword, Token method",
             "This     : Token keyword",
+            "Tuple    : Vec<Rc<Expr>> elements",
             "Unary    : Token operator, Rc<Expr> right",
+            "Update   : Rc<Expr> target, Token operator, bool prefix",
             "Variable : Token name",
         ],
     )?;
     define_ast(
         output_dir,
         "Stmt",
-        &["error", "expr", "token"],
+        &["error", "expr", "pattern", "token"],
         &["std::rc::Rc", "std::hash::{{Hash, Hasher}}"],
         &[
             "Block      : Rc<Vec<Rc<Stmt>>> statements",
-            "Class      : Token name, Option<Rc<Expr>> superclass, Rc<Vec<Rc<Stmt>>> methods",
+            "Class      : Token name, Option<Rc<Expr>> superclass, Vec<Rc<Expr>> traits, Rc<Vec<Rc<Stmt>>> methods, Rc<Vec<Rc<Stmt>>> class_methods, Rc<Vec<Rc<Stmt>>> getters, Rc<Vec<Rc<Stmt>>> setters",
             "Break      : Token token",
+            "Continue   : Token token",
+            "Destructure : Token brace, Vec<Token> names, Option<Token> rest, Rc<Expr> initializer, bool constant",
+            "Enum       : Token name, Vec<Token> variants, Vec<Option<Vec<Token>>> fields",
+            "Export     : Token keyword, Rc<Stmt> declaration",
             "Expression : Rc<Expr> expression",
-            "Function   : Token name, Rc<Vec<Token>> params, Rc<Vec<Rc<Stmt>>> body",
+            "ForIn      : Token name, Token keyword, Rc<Expr> iterable, Rc<Stmt> body",
+            "Function   : Token name, Rc<Vec<Token>> params, Rc<Vec<Option<Rc<Expr>>>> defaults, Option<Token> rest, Rc<Vec<Rc<Stmt>>> body, bool generator",
             "If         : Rc<Expr> condition, Rc<Stmt> then_branch, Option<Rc<Stmt>> else_branch",
+            "Import     : Token keyword, Token path, Option<Token> alias, Vec<Token> names, Vec<Token> aliases",
+            "Match      : Token keyword, Rc<Expr> subject, Rc<Vec<MatchArm>> arms, Option<Rc<Stmt>> else_branch",
             "Print      : Rc<Expr> expression",
             "Return     : Token keyword, Option<Rc<Expr>> value",
-            "Var        : Token name, Option<Rc<Expr>> initializer",
-            "While      : Rc<Expr> condition, Rc<Stmt> body",
+            "Trait      : Token name, Rc<Vec<Rc<Stmt>>> methods",
+            "Var        : Token name, Option<Rc<Expr>> initializer, bool constant",
+            "While      : Rc<Expr> condition, Rc<Stmt> body, Option<Rc<Expr>> increment",
+            "Yield      : Token keyword, Option<Rc<Expr>> value",
         ],
     )?;
     Ok(())
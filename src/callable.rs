@@ -6,53 +6,53 @@ use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use std::rc::Rc;
 
-#[derive(Clone)]
-pub struct Callable {
-    pub func: Rc<dyn LoxCallable>,
+/// how many arguments a callable accepts, `max` is None for variadic callables.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
 }
 
-pub trait LoxCallable {
-    fn call(
-        &self,
-        interpreter: &Interpreter,
-        arguments: Vec<Object>,
-        klass: Option<Rc<LoxClass>>,
-    ) -> Result<Object, LoxResult>;
-    fn arity(&self) -> usize;
-}
+impl Arity {
+    pub fn exactly(n: usize) -> Arity {
+        Arity {
+            min: n,
+            max: Some(n),
+        }
+    }
 
-impl LoxCallable for Callable {
-    fn call(
-        &self,
-        interpreter: &Interpreter,
-        arguments: Vec<Object>,
-        klass: Option<Rc<LoxClass>>,
-    ) -> Result<Object, LoxResult> {
-        self.func.call(interpreter, arguments, klass)
+    pub fn range(min: usize, max: usize) -> Arity {
+        Arity {
+            min,
+            max: Some(max),
+        }
     }
 
-    fn arity(&self) -> usize {
-        self.func.arity()
+    pub fn at_least(min: usize) -> Arity {
+        Arity { min, max: None }
     }
-}
 
-impl Display for Callable {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "<callable>")
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
     }
 }
 
-impl Debug for Callable {
+impl Display for Arity {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "<callable>")
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min),
+        }
     }
 }
 
-impl PartialEq for Callable {
-    fn eq(&self, other: &Self) -> bool {
-        std::ptr::eq(
-            Rc::as_ptr(&self.func) as *const (),
-            Rc::as_ptr(&other.func) as *const (),
-        )
-    }
+pub trait LoxCallable {
+    fn call(
+        &self,
+        interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult>;
+    fn arity(&self) -> Arity;
 }
@@ -3,12 +3,13 @@ use crate::object::Object;
 use crate::token::Token;
 use std::cell::RefCell;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Environment {
     values: HashMap<String, Object>,
+    constants: HashSet<String>,
     enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
@@ -16,6 +17,7 @@ impl Environment {
     pub fn new() -> Environment {
         Environment {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: None,
         }
     }
@@ -23,6 +25,7 @@ impl Environment {
     pub fn new_with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Environment {
         Environment {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: Some(enclosing),
         }
     }
@@ -31,6 +34,15 @@ impl Environment {
         self.values.insert(name, value);
     }
 
+    pub fn define_constant(&mut self, name: String, value: Object) {
+        self.constants.insert(name.clone());
+        self.values.insert(name, value);
+    }
+
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
     pub fn get_at(&self, distance: usize, name: &str) -> Result<Object, LoxResult> {
         if distance == 0 {
             Ok(self.values.get(name).unwrap().clone())
@@ -73,6 +85,24 @@ impl Environment {
             ))
         }
     }
+    /// globals are the outermost environment of the chain, which is the global scope of the
+    /// module (or script) this environment was created in.
+    pub fn get_global(&self, name: &Token) -> Result<Object, LoxResult> {
+        if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow().get_global(name)
+        } else {
+            self.get(name)
+        }
+    }
+
+    pub fn assign_global(&mut self, name: &Token, value: Object) -> Result<(), LoxResult> {
+        if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign_global(name, value)
+        } else {
+            self.assign(name, value)
+        }
+    }
+
     // pub fn get_by_name(&self, name: &str) -> bool {
     //     if let Some(_) = self.values.get(name) {
     //         true
@@ -84,6 +114,12 @@ impl Environment {
     // }
 
     pub fn assign(&mut self, name: &Token, value: Object) -> Result<(), LoxResult> {
+        if self.constants.contains(&name.as_string()) {
+            return Err(LoxResult::runtime_error(
+                name.dup(),
+                &format!("Cannot assign to constant '{}'.", &name.as_string()),
+            ));
+        }
         if let Entry::Occupied(mut object) = self.values.entry(name.as_string()) {
             object.insert(value);
             Ok(())
@@ -1,15 +1,33 @@
 use crate::object::Object;
 use crate::token::Token;
 use crate::token_type::TokenType;
+use std::rc::Rc;
 
 #[derive(Debug, PartialEq)]
 pub enum LoxResult {
-    ParseError { token: Token, message: String },
-    RuntimeError { token: Token, message: String },
-    Error { line: usize, message: String },
-    SystemError { message: String },
-    ReturnValue { value: Object },
+    ParseError {
+        token: Token,
+        message: String,
+    },
+    RuntimeError {
+        token: Token,
+        message: String,
+    },
+    Error {
+        line: usize,
+        module: Option<Rc<str>>,
+        message: String,
+    },
+    SystemError {
+        message: String,
+    },
+    ReturnValue {
+        value: Object,
+    },
     Break,
+    Continue,
+    // a `?.` that found nil, the optional chain around it evaluates to nil.
+    ShortCircuit,
     // Fail,
 }
 
@@ -18,10 +36,32 @@ impl LoxResult {
     pub fn error(line: usize, message: &str) -> LoxResult {
         LoxResult::Error {
             line,
+            module: None,
             message: message.to_string(),
         }
     }
 
+    /// an error that has no token of its own, reported where `token` is.
+    pub fn error_at(token: &Token, message: &str) -> LoxResult {
+        LoxResult::Error {
+            line: token.line,
+            module: token.module.clone(),
+            message: message.to_string(),
+        }
+    }
+
+    /// names the module a scanner error was found in, the other errors get it from their token.
+    pub fn in_module(self, module: &Option<Rc<str>>) -> LoxResult {
+        match self {
+            LoxResult::Error { line, message, .. } => LoxResult::Error {
+                line,
+                module: module.clone(),
+                message,
+            },
+            error => error,
+        }
+    }
+
     pub fn parse_error(token: Token, message: &str) -> LoxResult {
         LoxResult::ParseError {
             token: token.dup(),
@@ -49,34 +89,44 @@ impl LoxResult {
     pub fn report(&self) {
         match self {
             LoxResult::ParseError { token, message } => {
+                let location = location(token.line, &token.module);
                 if token.is(TokenType::Eof) {
-                    eprintln!("[line {}] Error at end: {}", token.line, message);
+                    eprintln!("{} Error at end: {}", location, message);
                 } else {
-                    eprintln!(
-                        "[line {}] Error at '{}': {}",
-                        token.line,
-                        token.as_string(),
-                        message
-                    );
+                    eprintln!("{} Error at '{}': {}", location, token.as_string(), message);
                 }
             }
             LoxResult::RuntimeError { token, message } => {
+                let location = location(token.line, &token.module);
                 if token.is(TokenType::Eof) {
-                    eprintln!("[line {}] Error at end: {}", token.line, message);
+                    eprintln!("{} Error at end: {}", location, message);
                 } else {
-                    eprintln!("[line {}] {}", token.line, message);
+                    eprintln!("{} {}", location, message);
                 }
             }
-            LoxResult::Error { line, message } => {
-                eprintln!("[line {}] Error: {}", line, message);
+            LoxResult::Error {
+                line,
+                module,
+                message,
+            } => {
+                eprintln!("{} Error: {}", location(*line, module), message);
             }
 
             LoxResult::SystemError { message } => {
                 eprintln!("System Error: {}", message);
             }
-            LoxResult::Break | LoxResult::ReturnValue { .. } => {} // LoxResult::Fail => {
-                                                                   //     panic!("should not get here")
-                                                                   // }
+            LoxResult::ShortCircuit => {}
+            LoxResult::Break | LoxResult::Continue | LoxResult::ReturnValue { .. } => {} // LoxResult::Fail => {
+                                                                                         //     panic!("should not get here")
+                                                                                         // }
         };
     }
 }
+
+// `[line N]`, errors in an imported module also name its file so it can be found.
+fn location(line: usize, module: &Option<Rc<str>>) -> String {
+    match module {
+        Some(module) => format!("[{}, line {}]", module, line),
+        None => format!("[line {}]", line),
+    }
+}
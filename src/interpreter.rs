@@ -3,22 +3,42 @@ use crate::environment::*;
 use crate::error::*;
 use crate::expr::*;
 use crate::lox_class::LoxClass;
+use crate::lox_enum::{LoxEnum, VariantConstructor};
 use crate::lox_function::LoxFunction;
+use crate::lox_generator::GeneratorMethod;
+use crate::lox_instance::LoxInstance;
+use crate::lox_iterator::LoxIterator;
+use crate::lox_list::LoxList;
+use crate::lox_map::LoxMap;
+use crate::lox_module::LoxModule;
+use crate::lox_range::LoxRange;
+use crate::lox_trait::LoxTrait;
+use crate::lox_tuple::LoxTuple;
 use crate::native_functions::*;
 use crate::object::*;
+use crate::parser::Parser;
+use crate::pattern::Pattern;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
 use crate::stmt::*;
 use crate::token::Token;
 use crate::token_type::*;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Interpreter {
-    pub globals: Rc<RefCell<Environment>>,
     environment: RefCell<Rc<RefCell<Environment>>>,
     locals: RefCell<HashMap<Rc<Expr>, usize>>,
+    // a module mapped to None is still being evaluated, importing it again is a cycle.
+    modules: RefCell<HashMap<PathBuf, Option<Rc<LoxModule>>>>,
+    current_path: RefCell<Option<PathBuf>>,
+    // the directory of the script being run, modules are named by their path from it.
+    script_dir: RefCell<Option<PathBuf>>,
+    exports: RefCell<HashSet<String>>,
 }
 
 impl StmtVisitor<()> for Interpreter {
@@ -41,42 +61,45 @@ impl StmtVisitor<()> for Interpreter {
             None
         };
 
-        self.environment
-            .borrow()
-            .borrow_mut()
-            .define(stmt.name.as_string(), Object::Nil);
-
-        let enclosing = if let Some(superclass) = sup.clone() {
-            let mut environment =
-                Environment::new_with_enclosing(self.environment.borrow().clone());
-            environment.define("super".to_string(), Object::Class(superclass));
-            Some(self.environment.replace(Rc::new(RefCell::new(environment))))
-        } else {
-            None
-        };
-
-        let mut methods = HashMap::new();
-        for method in stmt.methods.deref() {
-            if let Stmt::Function(func) = method.deref() {
-                let is_init = func.name.as_string() == "init";
-                let function = Object::Func(Rc::new(LoxFunction::new(
-                    func,
-                    &self.environment.borrow(),
-                    is_init,
-                )));
-                methods.insert(func.name.as_string(), function);
-            } else {
-                return Err(LoxResult::runtime_error(
-                    stmt.name.dup(),
-                    "Non-function method in class.",
-                ));
+        let mut traits = Vec::new();
+        for t in stmt.traits.iter() {
+            match self.evaluate(t.clone())? {
+                Object::Trait(t) => traits.push(t),
+                _ => {
+                    return Err(LoxResult::runtime_error(
+                        stmt.name.dup(),
+                        "Can only mix in traits.",
+                    ))
+                }
             }
         }
-        let klass = Rc::new(LoxClass::new(stmt.name.as_string(), sup, methods));
+        Self::check_traits(stmt, &traits)?;
 
-        if let Some(enclosing) = enclosing {
-            self.environment.replace(enclosing);
+        self.define_variable(&stmt.name, Object::Nil, false)?;
+
+        // methods close over the class environment, which holds `super` and the class itself
+        // under `#class` so private members are looked up in the class that declared them.
+        let mut environment = Environment::new_with_enclosing(self.environment.borrow().clone());
+        if let Some(superclass) = sup.clone() {
+            environment.define("super".to_string(), Object::Class(superclass));
         }
+        let environment = Rc::new(RefCell::new(environment));
+        let enclosing = self.environment.replace(Rc::clone(&environment));
+
+        let klass = Rc::new(LoxClass::new(
+            stmt.name.as_string(),
+            sup,
+            traits,
+            self.methods(&stmt.name, &stmt.methods, true)?,
+            self.methods(&stmt.name, &stmt.class_methods, false)?,
+            self.methods(&stmt.name, &stmt.getters, false)?,
+            self.methods(&stmt.name, &stmt.setters, false)?,
+        ));
+
+        self.environment.replace(enclosing);
+        environment
+            .borrow_mut()
+            .define("#class".to_string(), Object::Class(Rc::clone(&klass)));
 
         self.environment
             .borrow()
@@ -89,18 +112,90 @@ impl StmtVisitor<()> for Interpreter {
         Err(LoxResult::Break)
     }
 
+    fn visit_continue_stmt(&self, _: Rc<Stmt>, _stmt: &ContinueStmt) -> Result<(), LoxResult> {
+        Err(LoxResult::Continue)
+    }
+
+    fn visit_export_stmt(&self, _: Rc<Stmt>, stmt: &ExportStmt) -> Result<(), LoxResult> {
+        self.execute(stmt.declaration.clone())?;
+        let name = match stmt.declaration.deref() {
+            Stmt::Class(class) => class.name.as_string(),
+            Stmt::Function(func) => func.name.as_string(),
+            Stmt::Var(var) => var.name.as_string(),
+            Stmt::Enum(lox_enum) => lox_enum.name.as_string(),
+            Stmt::Trait(t) => t.name.as_string(),
+            Stmt::Destructure(destructure) => {
+                let names = destructure.names.iter().chain(destructure.rest.iter());
+                self.exports
+                    .borrow_mut()
+                    .extend(names.map(|name| name.as_string()));
+                return Ok(());
+            }
+            _ => {
+                return Err(LoxResult::runtime_error(
+                    stmt.keyword.dup(),
+                    "Can only export declarations.",
+                ))
+            }
+        };
+        self.exports.borrow_mut().insert(name);
+        Ok(())
+    }
+
+    fn visit_destructure_stmt(&self, _: Rc<Stmt>, stmt: &DestructureStmt) -> Result<(), LoxResult> {
+        let value = self.evaluate(stmt.initializer.clone())?;
+        let values = if stmt.brace.is(TokenType::LeftBrace) {
+            self.unpack_fields(value, stmt)?
+        } else {
+            self.unpack_elements(value, stmt)?
+        };
+        for (name, value) in stmt.names.iter().chain(stmt.rest.iter()).zip(values) {
+            self.define_variable(name, value, stmt.constant)?;
+        }
+        Ok(())
+    }
+
+    fn visit_enum_stmt(&self, _: Rc<Stmt>, stmt: &EnumStmt) -> Result<(), LoxResult> {
+        let variants = stmt
+            .variants
+            .iter()
+            .zip(stmt.fields.iter())
+            .map(|(variant, fields)| {
+                let fields = fields
+                    .as_ref()
+                    .map(|fields| fields.iter().map(|field| field.as_string()).collect());
+                (variant.as_string(), fields)
+            })
+            .collect();
+        let lox_enum = LoxEnum::new(stmt.name.as_string(), variants);
+        self.define_variable(&stmt.name, Object::Enum(Rc::new(lox_enum)), false)
+    }
+
     fn visit_expression_stmt(&self, _: Rc<Stmt>, stmt: &ExpressionStmt) -> Result<(), LoxResult> {
         self.evaluate(stmt.expression.clone())?;
         Ok(())
     }
 
+    // every iteration gets a fresh environment, so closures capture that iteration's value.
+    fn visit_forin_stmt(&self, _: Rc<Stmt>, stmt: &ForInStmt) -> Result<(), LoxResult> {
+        let iterable = self.evaluate(stmt.iterable.clone())?;
+        let mut iterator = self.iterator(iterable, &stmt.keyword)?;
+        while let Some(value) = self.iterator_next(&mut iterator, &stmt.keyword)? {
+            let mut environment =
+                Environment::new_with_enclosing(self.environment.borrow().clone());
+            environment.define(stmt.name.as_string(), value);
+            match self.execute_block(std::slice::from_ref(&stmt.body), environment) {
+                Err(LoxResult::Break) => break,
+                Err(LoxResult::Continue) | Ok(_) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
     fn visit_function_stmt(&self, _: Rc<Stmt>, stmt: &FunctionStmt) -> Result<(), LoxResult> {
         let function = LoxFunction::new(stmt, &self.environment.borrow(), false);
-        self.environment
-            .borrow()
-            .borrow_mut()
-            .define(stmt.name.as_string(), Object::Func(Rc::new(function)));
-        Ok(())
+        self.define_variable(&stmt.name, Object::Func(Rc::new(function)), false)
     }
 
     fn visit_if_stmt(&self, _: Rc<Stmt>, stmt: &IfStmt) -> Result<(), LoxResult> {
@@ -113,9 +208,29 @@ impl StmtVisitor<()> for Interpreter {
         }
     }
 
+    fn visit_import_stmt(&self, _: Rc<Stmt>, stmt: &ImportStmt) -> Result<(), LoxResult> {
+        let module = self.load_module(&stmt.keyword, &stmt.path)?;
+        if let Some(alias) = &stmt.alias {
+            self.define_variable(alias, Object::Module(Rc::clone(&module)), false)?;
+        }
+        for (name, alias) in stmt.names.iter().zip(stmt.aliases.iter()) {
+            let value = module.get(name)?;
+            self.define_variable(alias, value, false)?;
+        }
+        Ok(())
+    }
+
+    fn visit_match_stmt(&self, _: Rc<Stmt>, stmt: &MatchStmt) -> Result<(), LoxResult> {
+        let environment = self.environment.borrow().clone();
+        match self.match_arm(stmt, environment)? {
+            Some((body, scope)) => self.execute_block(std::slice::from_ref(&body), scope),
+            None => Ok(()),
+        }
+    }
+
     fn visit_print_stmt(&self, _: Rc<Stmt>, stmt: &PrintStmt) -> Result<(), LoxResult> {
         let value = self.evaluate(stmt.expression.clone())?;
-        println!("{}", value);
+        println!("{}", self.stringify(&value)?);
         Ok(())
     }
 
@@ -127,26 +242,39 @@ impl StmtVisitor<()> for Interpreter {
         }
     }
 
+    // a yield is run by the generator's own executor, it only gets here when it sits in a
+    // statement that executor hands back to the interpreter.
+    fn visit_yield_stmt(&self, _: Rc<Stmt>, stmt: &YieldStmt) -> Result<(), LoxResult> {
+        Err(LoxResult::runtime_error(
+            stmt.keyword.dup(),
+            "Can't yield from here.",
+        ))
+    }
+
+    fn visit_trait_stmt(&self, _: Rc<Stmt>, stmt: &TraitStmt) -> Result<(), LoxResult> {
+        let methods = self.methods(&stmt.name, &stmt.methods, false)?;
+        let t = LoxTrait::new(stmt.name.as_string(), methods);
+        self.define_variable(&stmt.name, Object::Trait(Rc::new(t)), false)
+    }
+
     fn visit_var_stmt(&self, _: Rc<Stmt>, stmt: &VarStmt) -> Result<(), LoxResult> {
         let value = if let Some(initializer) = stmt.initializer.clone() {
             self.evaluate(initializer)?
         } else {
             Object::Nil
         };
-
-        self.environment
-            .borrow()
-            .borrow_mut()
-            .define(stmt.name.as_string(), value);
-        Ok(())
+        self.define_variable(&stmt.name, value, stmt.constant)
     }
 
     fn visit_while_stmt(&self, _: Rc<Stmt>, stmt: &WhileStmt) -> Result<(), LoxResult> {
         while self.is_truthy(self.evaluate(stmt.condition.clone())?) {
             match self.execute(stmt.body.clone()) {
                 Err(LoxResult::Break) => break,
+                Err(LoxResult::Continue) | Ok(_) => {}
                 Err(e) => return Err(e),
-                Ok(_) => {}
+            }
+            if let Some(increment) = stmt.increment.clone() {
+                self.evaluate(increment)?;
             }
         }
         Ok(())
@@ -156,155 +284,94 @@ impl StmtVisitor<()> for Interpreter {
 impl ExprVisitor<Object> for Interpreter {
     fn visit_assign_expr(&self, warpper: Rc<Expr>, expr: &AssignExpr) -> Result<Object, LoxResult> {
         let value = self.evaluate(expr.value.clone())?;
-
-        if let Some(&distance) = self.locals.borrow().get(&warpper) {
-            self.environment.borrow().borrow_mut().assign_at(
-                distance,
-                &expr.name,
-                value.clone(),
-            )?;
-        } else {
-            self.globals
-                .borrow_mut()
-                .assign(&expr.name, value.clone())?;
-        }
+        self.assign_variable(&expr.name, warpper, value.clone())?;
         Ok(value)
     }
 
     fn visit_binary_expr(&self, _: Rc<Expr>, expr: &BinaryExpr) -> Result<Object, LoxResult> {
         let left = self.evaluate(expr.left.clone())?;
         let right = self.evaluate(expr.right.clone())?;
-        let op = expr.operator.token_type();
-        let result = match (left, right) {
-            (Object::Num(left), Object::Num(right)) => match op {
-                TokenType::Plus => Object::Num(left + right),
-                TokenType::Minus => Object::Num(left - right),
-                TokenType::Slash => Object::Num(left / right),
-                TokenType::Star => Object::Num(left * right),
-                TokenType::Greater => Object::Bool(left > right),
-                TokenType::GreaterEqual => Object::Bool(left >= right),
-                TokenType::Less => Object::Bool(left < right),
-                TokenType::LessEqual => Object::Bool(left <= right),
-                TokenType::Equal => Object::Bool(left == right),
-                TokenType::BangEqual => Object::Bool(left != right),
-                _ => {
-                    return Err(LoxResult::error(
-                        expr.operator.line,
-                        "Unreachable according to num binary expression",
-                    ));
-                }
-            },
-            (Object::Str(left), Object::Str(right)) => match op {
-                TokenType::Plus => Object::Str(left + &*right),
-                TokenType::Greater => Object::Bool(left > right),
-                TokenType::GreaterEqual => Object::Bool(left >= right),
-                TokenType::Less => Object::Bool(left < right),
-                TokenType::LessEqual => Object::Bool(left <= right),
-                TokenType::Equal => Object::Bool(left == right),
-                TokenType::BangEqual => Object::Bool(left != right),
-                _ => {
-                    return Err(LoxResult::error(
-                        expr.operator.line,
-                        "Unreachable according to string binary expression",
-                    ));
-                }
-            },
-            (Object::Str(left), Object::Num(right)) => match op {
-                TokenType::Plus => Object::Str(format!("{}{}", left, right)),
-                _ => {
-                    return Err(LoxResult::error(
-                        expr.operator.line,
-                        "Unreachable according to num and string binary expression",
-                    ));
-                }
-            },
-            (Object::Num(left), Object::Str(right)) => match op {
-                TokenType::Plus => Object::Str(format!("{}{}", left, right)),
-                _ => {
-                    return Err(LoxResult::error(
-                        expr.operator.line,
-                        "Unreachable according to string and num binary expression",
-                    ));
-                }
-            },
-            (Object::Nil, Object::Nil) => match op {
-                TokenType::Equal => Object::Bool(true),
-                TokenType::BangEqual => Object::Bool(false),
-                _ => {
-                    return Err(LoxResult::error(
-                        expr.operator.line,
-                        "Unreachable according to nil binary expression",
-                    ));
-                }
-            },
-            (Object::Nil, _) => match op {
-                TokenType::Equal => Object::Bool(false),
-                TokenType::BangEqual => Object::Bool(true),
-                _ => {
-                    return Err(LoxResult::error(
-                        expr.operator.line,
-                        "Unreachable according to nil eq other binary expression",
-                    ));
-                }
-            },
-            (Object::Bool(left), Object::Bool(right)) => match op {
-                TokenType::Equal => Object::Bool(left == right),
-                TokenType::BangEqual => Object::Bool(left != right),
-                _ => {
-                    return Err(LoxResult::error(
-                        expr.operator.line,
-                        "Unreachable according to bool binary expression",
-                    ));
-                }
-            },
-            _ => {
-                return Err(LoxResult::error(
-                    expr.operator.line,
-                    "Both operands of the comparison expression must be of the same type",
-                ))
-            }
-        };
-
-        if result == Object::ArithmeticError {
-            Err(LoxResult::runtime_error(
-                expr.operator.dup(),
-                "Illegal expression",
-            ))
-        } else {
-            Ok(result)
-        }
+        self.binary_op(left, &expr.operator, right)
     }
 
     fn visit_call_expr(&self, _: Rc<Expr>, expr: &CallExpr) -> Result<Object, LoxResult> {
         let callee = self.evaluate(expr.callee.clone())?;
 
         let mut arguments = Vec::new();
-        for argument in expr.arguments.clone() {
-            arguments.push(self.evaluate(argument)?);
+        let mut named = Vec::new();
+        for (argument, name) in expr.arguments.iter().zip(expr.names.iter()) {
+            let value = self.evaluate(argument.clone())?;
+            match name {
+                Some(name) => named.push((name.dup(), value)),
+                None => arguments.push(value),
+            }
+        }
+
+        // the Lox function the arguments bind to, which knows its parameter names. A class
+        // called with named arguments binds its initializer to the new instance first, as
+        // calling the class does, so defaults are evaluated where the initializer runs.
+        let has_named = !named.is_empty();
+        let function = match &callee {
+            Object::Func(f) => Some(Rc::clone(f)),
+            Object::Class(c) if has_named => c.initializer().and_then(|init| {
+                let instance = Object::Instance(Rc::new(LoxInstance::new(Rc::clone(c))));
+                match init.bind(&instance) {
+                    Object::Func(init) => Some(init),
+                    _ => None,
+                }
+            }),
+            Object::Class(c) => c.initializer(),
+            Object::Instance(instance) => self.bind_special(instance, "__call__"),
+            _ => None,
+        };
+        if !named.is_empty() {
+            match &function {
+                Some(function) => {
+                    arguments = function.arrange_arguments(self, arguments, named, &expr.paren)?;
+                }
+                None => {
+                    return Err(LoxResult::runtime_error(
+                        expr.paren.dup(),
+                        "Only functions declared in Lox take named arguments.",
+                    ))
+                }
+            }
         }
 
         let (callfunc, klass): (Option<Rc<dyn LoxCallable>>, Option<Rc<LoxClass>>) = match callee {
             Object::Func(f) => (Some(f), None),
             Object::Native(n) => (Some(n.func.clone()), None),
+            Object::Variant(variant) => (Some(Rc::new(VariantConstructor { variant }) as _), None),
+            // the bound initializer returns the instance it was bound to.
+            Object::Class(c) if has_named => (function.map(|f| f as Rc<dyn LoxCallable>), Some(c)),
             Object::Class(c) => {
                 let klass = Rc::clone(&c);
                 (Some(c), Some(klass))
             }
+            Object::Instance(_) => match function.clone() {
+                Some(f) => (Some(f as Rc<dyn LoxCallable>), None),
+                None => (None, None),
+            },
             _ => (None, None),
         };
 
         if let Some(callfunc) = callfunc {
-            if arguments.len() != callfunc.arity() {
+            let arity = callfunc.arity();
+            if !arity.accepts(arguments.len()) {
                 return Err(LoxResult::runtime_error(
                     expr.paren.dup(),
-                    &format!(
-                        "Expected {} arguments but got {}.",
-                        callfunc.arity(),
-                        arguments.len()
-                    ),
+                    &format!("Expected {} arguments but got {}.", arity, arguments.len()),
                 ));
             }
-            callfunc.call(self, arguments, klass)
+            // natives have no token to report their errors at, they use the call's.
+            callfunc
+                .call(self, arguments, klass)
+                .map_err(|error| match error {
+                    LoxResult::SystemError { message } => {
+                        LoxResult::runtime_error(expr.paren.dup(), &message)
+                    }
+                    error => error,
+                })
         } else {
             Err(LoxResult::runtime_error(
                 expr.paren.dup(),
@@ -313,22 +380,129 @@ impl ExprVisitor<Object> for Interpreter {
         }
     }
 
+    fn visit_compound_expr(&self, _: Rc<Expr>, expr: &CompoundExpr) -> Result<Object, LoxResult> {
+        let ttype = match expr.operator.token_type() {
+            TokenType::PlusAssign => TokenType::Plus,
+            TokenType::MinusAssign => TokenType::Minus,
+            TokenType::StarAssign => TokenType::Star,
+            TokenType::SlashAssign => TokenType::Slash,
+            _ => TokenType::Percent,
+        };
+        let operator = expr
+            .operator
+            .stand_in(ttype, expr.operator.lexeme.trim_end_matches('='));
+        let (_, value) = self.update_target(&expr.target, &expr.operator, |current| {
+            let value = self.evaluate(expr.value.clone())?;
+            self.binary_op(current, &operator, value)
+        })?;
+        Ok(value)
+    }
+
+    fn visit_conditional_expr(
+        &self,
+        _: Rc<Expr>,
+        expr: &ConditionalExpr,
+    ) -> Result<Object, LoxResult> {
+        if self.is_truthy(self.evaluate(expr.condition.clone())?) {
+            self.evaluate(expr.then_branch.clone())
+        } else {
+            self.evaluate(expr.else_branch.clone())
+        }
+    }
+
     fn visit_get_expr(&self, _wrapper: Rc<Expr>, expr: &GetExpr) -> Result<Object, LoxResult> {
+        if expr.name.is(TokenType::PrivateName) {
+            return self.get_private(&expr.object, &expr.name);
+        }
         let object = self.evaluate(expr.object.clone())?;
-        if let Object::Instance(instance) = object {
-            instance.get(&expr.name, &instance)
-        } else {
-            Err(LoxResult::runtime_error(
-                expr.name.dup(),
-                "Only instances have properties.",
-            ))
+        if expr.optional && object == Object::Nil {
+            return Err(LoxResult::ShortCircuit);
         }
+        self.get_property(object, &expr.name)
     }
 
     fn visit_grouping_expr(&self, _: Rc<Expr>, expr: &GroupingExpr) -> Result<Object, LoxResult> {
         self.evaluate(expr.expression.clone())
     }
 
+    fn visit_index_expr(&self, _: Rc<Expr>, expr: &IndexExpr) -> Result<Object, LoxResult> {
+        let object = self.evaluate(expr.object.clone())?;
+        let index = self.evaluate(expr.index.clone())?;
+        self.get_index(object, &expr.bracket, index)
+    }
+
+    fn visit_interpolation_expr(
+        &self,
+        _: Rc<Expr>,
+        expr: &InterpolationExpr,
+    ) -> Result<Object, LoxResult> {
+        let mut result = String::new();
+        for part in expr.parts.iter() {
+            let value = self.evaluate(part.clone())?;
+            result.push_str(&self.stringify(&value)?);
+        }
+        Ok(Object::Str(result))
+    }
+
+    fn visit_slice_expr(&self, _: Rc<Expr>, expr: &SliceExpr) -> Result<Object, LoxResult> {
+        let object = self.evaluate(expr.object.clone())?;
+        let mut bounds = [None, None];
+        for (bound, value) in [&expr.start, &expr.end].iter().zip(bounds.iter_mut()) {
+            if let Some(bound) = bound {
+                *value = match self.evaluate(bound.clone())? {
+                    Object::Nil => None,
+                    bound => Some(Self::as_index(&bound).ok_or_else(|| {
+                        LoxResult::runtime_error(
+                            expr.bracket.dup(),
+                            "Slice bounds must be integers.",
+                        )
+                    })?),
+                };
+            }
+        }
+
+        match object {
+            Object::List(list) => {
+                let elements = list.elements.borrow();
+                let (start, end) = Self::slice_range(bounds, elements.len());
+                Ok(Object::List(Rc::new(LoxList::new(
+                    elements[start..end].to_vec(),
+                ))))
+            }
+            Object::Tuple(tuple) => {
+                let (start, end) = Self::slice_range(bounds, tuple.len());
+                Ok(Object::Tuple(Rc::new(LoxTuple::new(
+                    tuple.elements[start..end].to_vec(),
+                ))))
+            }
+            Object::Str(string) => {
+                let chars = string.chars().collect::<Vec<char>>();
+                let (start, end) = Self::slice_range(bounds, chars.len());
+                Ok(Object::Str(chars[start..end].iter().collect()))
+            }
+            _ => Err(LoxResult::runtime_error(
+                expr.bracket.dup(),
+                "Only lists, tuples and strings can be sliced.",
+            )),
+        }
+    }
+
+    fn visit_list_expr(&self, _: Rc<Expr>, expr: &ListExpr) -> Result<Object, LoxResult> {
+        let mut elements = Vec::new();
+        for element in expr.elements.iter() {
+            elements.push(self.evaluate(element.clone())?);
+        }
+        Ok(Object::List(Rc::new(LoxList::new(elements))))
+    }
+
+    fn visit_tuple_expr(&self, _: Rc<Expr>, expr: &TupleExpr) -> Result<Object, LoxResult> {
+        let mut elements = Vec::new();
+        for element in expr.elements.iter() {
+            elements.push(self.evaluate(element.clone())?);
+        }
+        Ok(Object::Tuple(Rc::new(LoxTuple::new(elements))))
+    }
+
     fn visit_literal_expr(&self, _: Rc<Expr>, expr: &LiteralExpr) -> Result<Object, LoxResult> {
         Ok(expr.value.clone().unwrap())
     }
@@ -341,6 +515,11 @@ impl ExprVisitor<Object> for Interpreter {
             if self.is_truthy(left.clone()) {
                 return Ok(left);
             }
+        } else if expr.operator.is(TokenType::QuestionQuestion) {
+            // ??
+            if left != Object::Nil {
+                return Ok(left);
+            }
         } else if !self.is_truthy(left.clone()) {
             // and
             return Ok(left);
@@ -348,19 +527,45 @@ impl ExprVisitor<Object> for Interpreter {
         self.evaluate(expr.right.clone())
     }
 
-    fn visit_set_expr(&self, _wrapper: Rc<Expr>, expr: &SetExpr) -> Result<Object, LoxResult> {
-        let object = self.evaluate(expr.object.clone())?;
+    fn visit_map_expr(&self, _: Rc<Expr>, expr: &MapExpr) -> Result<Object, LoxResult> {
+        let map = LoxMap::new();
+        for (key, value) in expr.keys.iter().zip(expr.values.iter()) {
+            let key = self.evaluate(key.clone())?;
+            let value = self.evaluate(value.clone())?;
+            self.map_insert(&map, key, value, &expr.brace)?;
+        }
+        Ok(Object::Map(Rc::new(map)))
+    }
 
-        if let Object::Instance(instance) = object {
+    fn visit_optionalchain_expr(
+        &self,
+        _: Rc<Expr>,
+        expr: &OptionalChainExpr,
+    ) -> Result<Object, LoxResult> {
+        match self.evaluate(expr.expression.clone()) {
+            Err(LoxResult::ShortCircuit) => Ok(Object::Nil),
+            result => result,
+        }
+    }
+
+    fn visit_set_expr(&self, _wrapper: Rc<Expr>, expr: &SetExpr) -> Result<Object, LoxResult> {
+        if expr.name.is(TokenType::PrivateName) {
             let value = self.evaluate(expr.value.clone())?;
-            instance.set(&expr.name, value.clone());
-            Ok(value)
-        } else {
-            Err(LoxResult::runtime_error(
-                expr.name.dup(),
-                "Only instances have fields.",
-            ))
+            self.set_private(&expr.object, &expr.name, value.clone())?;
+            return Ok(value);
         }
+        let object = self.evaluate(expr.object.clone())?;
+        let value = self.evaluate(expr.value.clone())?;
+        self.set_property(object, &expr.name, value.clone())?;
+        Ok(value)
+    }
+
+    fn visit_setindex_expr(&self, _: Rc<Expr>, expr: &SetIndexExpr) -> Result<Object, LoxResult> {
+        let object = self.evaluate(expr.object.clone())?;
+        let index = self.evaluate(expr.index.clone())?;
+        let value = self.evaluate(expr.value.clone())?;
+        self.set_index(object, &expr.bracket, index, value.clone())?;
+        Ok(value)
     }
 
     fn visit_super_expr(&self, wrapper: Rc<Expr>, expr: &SuperExpr) -> Result<Object, LoxResult> {
@@ -388,20 +593,20 @@ impl ExprVisitor<Object> for Interpreter {
             if let Object::Func(func) = method.clone() {
                 Ok(func.bind(&object))
             } else {
-                return Err(LoxResult::runtime_error(
+                Err(LoxResult::runtime_error(
                     expr.method.dup(),
                     &format!("Properties '{}' not a method.", expr.method.as_string()),
-                ));
+                ))
             }
         } else {
-            return Err(LoxResult::runtime_error(
+            Err(LoxResult::runtime_error(
                 expr.method.dup(),
                 &format!(
                     "Undefined method '{}' in superclass '{}'.",
                     expr.method.as_string(),
                     superclass
                 ),
-            ));
+            ))
         }
     }
 
@@ -412,19 +617,52 @@ impl ExprVisitor<Object> for Interpreter {
     fn visit_unary_expr(&self, _: Rc<Expr>, expr: &UnaryExpr) -> Result<Object, LoxResult> {
         let right = self.evaluate(expr.right.clone())?;
 
+        if let Object::Instance(instance) = &right {
+            if expr.operator.is(TokenType::Minus) {
+                if let Some(result) =
+                    self.call_special(instance, "__neg__", &expr.operator, Vec::new())?
+                {
+                    return Ok(result);
+                }
+            }
+        }
+
         match expr.operator.token_type() {
             TokenType::Minus => match right {
                 Object::Num(n) => Ok(Object::Num(-n)),
+                Object::Int(n) => n.checked_neg().map(Object::Int).ok_or_else(|| {
+                    LoxResult::runtime_error(expr.operator.dup(), "Integer overflow.")
+                }),
                 _ => Ok(Object::Nil),
             },
+            TokenType::Tilde => match right {
+                Object::Int(n) => Ok(Object::Int(!n)),
+                _ => Err(LoxResult::runtime_error(
+                    expr.operator.dup(),
+                    "Operand of '~' must be an integer.",
+                )),
+            },
             TokenType::Bang => Ok(Object::Bool(!self.is_truthy(right))),
-            _ => Err(LoxResult::error(
-                expr.operator.line,
+            _ => Err(LoxResult::error_at(
+                &expr.operator,
                 "Unreachable according to Unary expression",
             )),
         }
     }
 
+    fn visit_update_expr(&self, _: Rc<Expr>, expr: &UpdateExpr) -> Result<Object, LoxResult> {
+        let (ttype, lexeme) = if expr.operator.is(TokenType::PlusPlus) {
+            (TokenType::Plus, "+")
+        } else {
+            (TokenType::Minus, "-")
+        };
+        let operator = expr.operator.stand_in(ttype, lexeme);
+        let (old, new) = self.update_target(&expr.target, &expr.operator, |current| {
+            self.binary_op(current, &operator, Object::Int(1))
+        })?;
+        Ok(if expr.prefix { new } else { old })
+    }
+
     fn visit_variable_expr(
         &self,
         wrapper: Rc<Expr>,
@@ -436,36 +674,434 @@ impl ExprVisitor<Object> for Interpreter {
 
 impl Interpreter {
     pub fn new() -> Interpreter {
-        let globals = Rc::new(RefCell::new(Environment::new()));
-        globals.borrow_mut().define(
-            "clock".to_string(),
-            Object::Native(Rc::new(LoxNative {
-                func: Rc::new(NativeClock {}),
-            })),
-        );
         Interpreter {
-            globals: Rc::clone(&globals),
-            environment: RefCell::new(Rc::clone(&globals)),
+            environment: RefCell::new(Rc::new(RefCell::new(Interpreter::global_environment()))),
             locals: RefCell::new(HashMap::new()),
+            modules: RefCell::new(HashMap::new()),
+            current_path: RefCell::new(None),
+            script_dir: RefCell::new(None),
+            exports: RefCell::new(HashSet::new()),
         }
     }
 
-    fn evaluate(&self, expr: Rc<Expr>) -> Result<Object, LoxResult> {
-        // if let Err(e) = self.check_global_function("clock") {
-        //     return Err(e);
-        // }
-        expr.accept(expr.clone(), self)
+    // every module gets its own globals, with the native functions already defined.
+    fn global_environment() -> Environment {
+        let mut globals = Environment::new();
+        let natives: [(&str, Rc<dyn LoxCallable>); 14] = [
+            ("clock", Rc::new(NativeClock {})),
+            ("repr", Rc::new(NativeRepr {})),
+            ("type", Rc::new(NativeType {})),
+            ("classOf", Rc::new(NativeClassOf {})),
+            ("superclassOf", Rc::new(NativeSuperclassOf {})),
+            ("fields", Rc::new(NativeFields {})),
+            ("methods", Rc::new(NativeMethods {})),
+            ("hasField", Rc::new(NativeHasField {})),
+            ("getField", Rc::new(NativeGetField {})),
+            ("setField", Rc::new(NativeSetField {})),
+            ("deleteField", Rc::new(NativeDeleteField {})),
+            ("max", Rc::new(NativeMax {})),
+            ("min", Rc::new(NativeMin {})),
+            ("format", Rc::new(NativeFormat {})),
+        ];
+        for (name, func) in natives {
+            globals.define(
+                name.to_string(),
+                Object::Native(Rc::new(LoxNative { func })),
+            );
+        }
+        globals
     }
 
-    fn execute(&self, stmt: Rc<Stmt>) -> Result<(), LoxResult> {
-        // println!("{:?}", &stmt);
-        stmt.accept(stmt.clone(), self)
+    /// the script being run, imports are resolved relative to its directory.
+    pub fn set_script_path(&self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.modules.borrow_mut().insert(path.clone(), None);
+        self.script_dir
+            .replace(path.parent().map(|dir| dir.to_path_buf()));
+        self.current_path.replace(Some(path));
     }
 
-    pub fn execute_block(
-        &self,
-        statements: &[Rc<Stmt>],
-        env: Environment,
+    // how errors in a module name it, relative to the script's directory when it's inside it.
+    fn module_name(&self, full_path: &Path) -> Rc<str> {
+        let script_dir = self.script_dir.borrow();
+        let path = match script_dir.as_ref() {
+            Some(dir) => full_path.strip_prefix(dir).unwrap_or(full_path),
+            None => full_path,
+        };
+        Rc::from(path.to_string_lossy().as_ref())
+    }
+
+    fn load_module(&self, keyword: &Token, path: &Token) -> Result<Rc<LoxModule>, LoxResult> {
+        let relative = match &path.literal {
+            Some(literal) => literal.to_string(),
+            None => path.as_string(),
+        };
+        let full_path = match self.current_path.borrow().as_ref().and_then(|p| p.parent()) {
+            Some(dir) => dir.join(&relative),
+            None => PathBuf::from(&relative),
+        };
+        let full_path = full_path.canonicalize().map_err(|e| {
+            LoxResult::runtime_error(
+                path.dup(),
+                &format!("Cannot open module '{}': {}.", relative, e),
+            )
+        })?;
+
+        match self.modules.borrow().get(&full_path) {
+            Some(Some(module)) => return Ok(Rc::clone(module)),
+            Some(None) => {
+                return Err(LoxResult::runtime_error(
+                    keyword.dup(),
+                    &format!("Circular import of module '{}'.", relative),
+                ))
+            }
+            None => {}
+        }
+
+        self.modules.borrow_mut().insert(full_path.clone(), None);
+        let result = self.evaluate_module(path, &relative, &full_path);
+        match &result {
+            Ok(module) => self
+                .modules
+                .borrow_mut()
+                .insert(full_path, Some(Rc::clone(module))),
+            Err(_) => self.modules.borrow_mut().remove(&full_path),
+        };
+        result
+    }
+
+    fn evaluate_module(
+        &self,
+        path: &Token,
+        relative: &str,
+        full_path: &Path,
+    ) -> Result<Rc<LoxModule>, LoxResult> {
+        let failed = || {
+            LoxResult::runtime_error(
+                path.dup(),
+                &format!("Failed to load module '{}'.", relative),
+            )
+        };
+        let source = std::fs::read_to_string(full_path).map_err(|e| {
+            LoxResult::runtime_error(
+                path.dup(),
+                &format!("Cannot open module '{}': {}.", relative, e),
+            )
+        })?;
+
+        let mut scanner = Scanner::new_in_module(source, self.module_name(full_path));
+        let tokens = scanner.scan_tokens().map_err(|_| failed())?;
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse()?;
+        if !parser.success() {
+            return Err(failed());
+        }
+        let resolver = Resolver::new(self);
+        resolver.resolve(&Rc::new(statements.clone()))?;
+        if !resolver.success() {
+            return Err(failed());
+        }
+
+        let globals = Rc::new(RefCell::new(Interpreter::global_environment()));
+        let previous_env = self.environment.replace(Rc::clone(&globals));
+        let previous_path = self.current_path.replace(Some(full_path.to_path_buf()));
+        let previous_exports = self.exports.replace(HashSet::new());
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.execute(statement.clone()));
+        self.environment.replace(previous_env);
+        self.current_path.replace(previous_path);
+        let exports = self.exports.replace(previous_exports);
+        result?;
+
+        let name = full_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| relative.to_string());
+        Ok(Rc::new(LoxModule::new(name, globals, exports)))
+    }
+
+    fn evaluate(&self, expr: Rc<Expr>) -> Result<Object, LoxResult> {
+        // if let Err(e) = self.check_global_function("clock") {
+        //     return Err(e);
+        // }
+        expr.accept(expr.clone(), self)
+    }
+
+    /// evaluates an expression as if it were written where `environment` is in scope, as the
+    /// default values of parameters are.
+    pub fn evaluate_in(
+        &self,
+        expr: Rc<Expr>,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Object, LoxResult> {
+        let previous = self.environment.replace(environment);
+        let result = self.evaluate(expr);
+        self.environment.replace(previous);
+        result
+    }
+
+    fn define_variable(
+        &self,
+        name: &Token,
+        value: Object,
+        constant: bool,
+    ) -> Result<(), LoxResult> {
+        let environment = self.environment.borrow();
+        let mut environment = environment.borrow_mut();
+        if environment.is_constant(&name.as_string()) {
+            return Err(LoxResult::runtime_error(
+                name.dup(),
+                &format!("Cannot redefine constant '{}'.", name.as_string()),
+            ));
+        }
+        if constant {
+            environment.define_constant(name.as_string(), value);
+        } else {
+            environment.define(name.as_string(), value);
+        }
+        Ok(())
+    }
+
+    // `var [a, b, ...rest] = value;` takes the elements of a list or tuple in order, the rest
+    // go into a new list.
+    fn unpack_elements(
+        &self,
+        value: Object,
+        stmt: &DestructureStmt,
+    ) -> Result<Vec<Object>, LoxResult> {
+        let mut elements = match value {
+            Object::List(list) => list.elements.borrow().clone(),
+            Object::Tuple(tuple) => tuple.elements.clone(),
+            _ => {
+                return Err(LoxResult::runtime_error(
+                    stmt.brace.dup(),
+                    "Can only unpack a list or tuple into '[...]'.",
+                ))
+            }
+        };
+        let wanted = stmt.names.len();
+        if stmt.rest.is_some() && elements.len() >= wanted {
+            let rest = elements.split_off(wanted);
+            elements.push(Object::List(Rc::new(LoxList::new(rest))));
+        } else if stmt.rest.is_some() || elements.len() != wanted {
+            let at_least = if stmt.rest.is_some() { "at least " } else { "" };
+            return Err(LoxResult::runtime_error(
+                stmt.brace.dup(),
+                &format!(
+                    "Expected {}{} values to unpack but got {}.",
+                    at_least,
+                    wanted,
+                    elements.len()
+                ),
+            ));
+        }
+        Ok(elements)
+    }
+
+    // `var {x, y} = value;` reads each name like `value.x` would, or as a key of a map.
+    fn unpack_fields(
+        &self,
+        value: Object,
+        stmt: &DestructureStmt,
+    ) -> Result<Vec<Object>, LoxResult> {
+        let mut values = Vec::new();
+        for name in stmt.names.iter() {
+            let field = match &value {
+                Object::Instance(_) => self.get_property(value.clone(), name)?,
+                Object::Map(map) => {
+                    let key = Object::Str(name.as_string());
+                    match self.map_find(map, &key, name)? {
+                        Some(i) => map.get_at(i),
+                        None => {
+                            return Err(LoxResult::runtime_error(
+                                name.dup(),
+                                &format!("Undefined key '{}'.", name.as_string()),
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(LoxResult::runtime_error(
+                        stmt.brace.dup(),
+                        "Can only unpack an instance or map into '{...}'.",
+                    ))
+                }
+            };
+            values.push(field);
+        }
+        Ok(values)
+    }
+
+    /// picks the body of a match statement to run in `environment`: the first case with a
+    /// pattern matching the value, or else the `else` case. The body comes with the scope
+    /// holding the bindings of the pattern.
+    pub fn match_arm(
+        &self,
+        stmt: &MatchStmt,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Option<(Rc<Stmt>, Environment)>, LoxResult> {
+        let previous = self.environment.replace(Rc::clone(&environment));
+        let result = self.select_arm(stmt, environment);
+        self.environment.replace(previous);
+        result
+    }
+
+    fn select_arm(
+        &self,
+        stmt: &MatchStmt,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Option<(Rc<Stmt>, Environment)>, LoxResult> {
+        let value = self.evaluate(stmt.subject.clone())?;
+        for arm in stmt.arms.iter() {
+            for pattern in arm.patterns.iter() {
+                let mut bindings = Vec::new();
+                if self.pattern_matches(pattern, &value, &stmt.keyword, &mut bindings)? {
+                    let mut scope = Environment::new_with_enclosing(environment);
+                    for (name, value) in bindings {
+                        scope.define(name, value);
+                    }
+                    return Ok(Some((Rc::clone(&arm.body), scope)));
+                }
+            }
+        }
+        Ok(stmt
+            .else_branch
+            .clone()
+            .map(|body| (body, Environment::new_with_enclosing(environment))))
+    }
+
+    fn pattern_matches(
+        &self,
+        pattern: &Pattern,
+        value: &Object,
+        keyword: &Token,
+        bindings: &mut Vec<(String, Object)>,
+    ) -> Result<bool, LoxResult> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Binding(name) => {
+                bindings.push((name.as_string(), value.clone()));
+                Ok(true)
+            }
+            Pattern::Literal(literal) => self.values_equal(value, literal, keyword),
+            Pattern::Value(expr) => {
+                let expected = self.evaluate(expr.clone())?;
+                self.values_equal(value, &expected, keyword)
+            }
+            Pattern::Class {
+                class,
+                paren,
+                fields,
+            } => {
+                // what the inner patterns are matched against, in order.
+                let (name, available): (String, Vec<Object>) = match self.evaluate(class.clone())? {
+                    Object::Class(klass) => {
+                        let instance = match value {
+                            Object::Instance(instance)
+                                if instance.class().is_subclass_of(&klass) =>
+                            {
+                                instance
+                            }
+                            _ => return Ok(false),
+                        };
+                        let values = Self::pattern_fields(&klass, instance, paren, fields.len())?;
+                        (format!("class '{}'", klass.name()), values)
+                    }
+                    Object::Variant(variant) => match value {
+                        Object::EnumValue(value) if Rc::ptr_eq(&value.variant, &variant) => {
+                            let values = value.values.clone();
+                            (format!("variant '{}'", value.name()), values)
+                        }
+                        _ => return Ok(false),
+                    },
+                    // `Shape()` matches any value of the enum.
+                    Object::Enum(lox_enum) => match value {
+                        Object::EnumValue(value) if lox_enum.has_value(value) => {
+                            (format!("enum '{}'", lox_enum.name()), Vec::new())
+                        }
+                        _ => return Ok(false),
+                    },
+                    _ => {
+                        return Err(LoxResult::runtime_error(
+                            paren.dup(),
+                            "Can only match against a class, enum or enum variant.",
+                        ))
+                    }
+                };
+
+                if fields.len() > available.len() {
+                    return Err(LoxResult::runtime_error(
+                        paren.dup(),
+                        &format!(
+                            "Pattern for {} expects at most {} fields but got {}.",
+                            name,
+                            available.len(),
+                            fields.len()
+                        ),
+                    ));
+                }
+                for (field, field_value) in fields.iter().zip(available) {
+                    if !self.pattern_matches(field, &field_value, keyword, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    // the first `count` fields named after the initializer's parameters. An initializer that
+    // doesn't keep a parameter in a field of the same name can't be matched positionally, which
+    // is an error rather than a pattern that silently never matches.
+    fn pattern_fields(
+        klass: &LoxClass,
+        instance: &LoxInstance,
+        paren: &Token,
+        count: usize,
+    ) -> Result<Vec<Object>, LoxResult> {
+        let params = klass
+            .initializer()
+            .map_or(Vec::new(), |init| init.params().to_vec());
+        params
+            .iter()
+            .take(count)
+            .map(|param| {
+                instance.get_field(&param.as_string()).ok_or_else(|| {
+                    LoxResult::runtime_error(
+                        paren.dup(),
+                        &format!(
+                            "Class '{}' has no field '{}' for its initializer parameter.",
+                            klass.name(),
+                            param.as_string()
+                        ),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// runs a statement in `environment`, for generators which keep their own environments.
+    pub fn execute_in(
+        &self,
+        stmt: Rc<Stmt>,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<(), LoxResult> {
+        let previous = self.environment.replace(environment);
+        let result = self.execute(stmt);
+        self.environment.replace(previous);
+        result
+    }
+
+    fn execute(&self, stmt: Rc<Stmt>) -> Result<(), LoxResult> {
+        // println!("{:?}", &stmt);
+        stmt.accept(stmt.clone(), self)
+    }
+
+    pub fn execute_block(
+        &self,
+        statements: &[Rc<Stmt>],
+        env: Environment,
     ) -> Result<(), LoxResult> {
         let previous = self.environment.replace(Rc::new(RefCell::new(env)));
         let result = statements
@@ -485,7 +1121,150 @@ impl Interpreter {
     //     }
     // }
 
-    fn is_truthy(&self, right: Object) -> bool {
+    /// the text `print`, interpolation and string concatenation show for a value, using the
+    /// `toString()` method of its class when there is one.
+    pub fn stringify(&self, value: &Object) -> Result<String, LoxResult> {
+        self.format_value(value, false, &mut Vec::new())
+    }
+
+    /// the debug form of a value: strings are quoted and instances always show their fields.
+    pub fn repr(&self, value: &Object) -> Result<String, LoxResult> {
+        self.format_value(value, true, &mut Vec::new())
+    }
+
+    // `seen` holds the lists, maps and instances currently being printed, so a value that
+    // contains itself prints as `...` instead of recursing forever.
+    fn format_value(
+        &self,
+        value: &Object,
+        debug: bool,
+        seen: &mut Vec<*const ()>,
+    ) -> Result<String, LoxResult> {
+        let ptr = match value {
+            Object::List(list) => Rc::as_ptr(list) as *const (),
+            Object::Tuple(tuple) => Rc::as_ptr(tuple) as *const (),
+            Object::EnumValue(value) if !value.values.is_empty() => Rc::as_ptr(value) as *const (),
+            Object::Map(map) => Rc::as_ptr(map) as *const (),
+            Object::Instance(instance) => Rc::as_ptr(instance) as *const (),
+            Object::Str(string) if debug => return Ok(Self::quote(string)),
+            Object::Class(klass) if !debug => {
+                if let Some(Object::Func(method)) = klass.find_class_method(&"toString".to_string())
+                {
+                    if let Object::Func(method) = method.bind(value) {
+                        return self.call_to_string(&method);
+                    }
+                }
+                return Ok(value.to_string());
+            }
+            _ => return Ok(value.to_string()),
+        };
+        if seen.contains(&ptr) {
+            return Ok("...".to_string());
+        }
+        if let (Object::Instance(instance), false) = (value, debug) {
+            if let Some(method) = self.bind_special(instance, "toString") {
+                return self.call_to_string(&method);
+            }
+        }
+
+        seen.push(ptr);
+        let result = match value {
+            Object::List(list) => {
+                let elements = list.elements.borrow().clone();
+                let elements = elements
+                    .iter()
+                    .map(|e| self.format_value(e, debug, seen))
+                    .collect::<Result<Vec<String>, LoxResult>>()?;
+                format!("[{}]", elements.join(", "))
+            }
+            Object::Tuple(tuple) => {
+                let elements = tuple
+                    .elements
+                    .iter()
+                    .map(|e| self.format_value(e, debug, seen))
+                    .collect::<Result<Vec<String>, LoxResult>>()?;
+                if elements.len() == 1 {
+                    format!("({},)", elements[0])
+                } else {
+                    format!("({})", elements.join(", "))
+                }
+            }
+            Object::EnumValue(value) => {
+                let values = value
+                    .values
+                    .iter()
+                    .map(|v| self.format_value(v, debug, seen))
+                    .collect::<Result<Vec<String>, LoxResult>>()?;
+                format!("{}({})", value.name(), values.join(", "))
+            }
+            Object::Map(map) => {
+                let entries = map.entries.borrow().clone();
+                let entries = entries
+                    .iter()
+                    .map(|(k, v)| {
+                        Ok(format!(
+                            "{}: {}",
+                            self.format_value(k, debug, seen)?,
+                            self.format_value(v, debug, seen)?
+                        ))
+                    })
+                    .collect::<Result<Vec<String>, LoxResult>>()?;
+                format!("{{{}}}", entries.join(", "))
+            }
+            Object::Instance(instance) => {
+                let fields = instance
+                    .fields()
+                    .iter()
+                    .map(|(k, v)| Ok(format!("{}: {}", k, self.format_value(v, debug, seen)?)))
+                    .collect::<Result<Vec<String>, LoxResult>>()?;
+                if fields.is_empty() {
+                    format!("{} {{}}", instance.class().name())
+                } else {
+                    format!("{} {{ {} }}", instance.class().name(), fields.join(", "))
+                }
+            }
+            _ => unreachable!(),
+        };
+        seen.pop();
+        Ok(result)
+    }
+
+    fn call_to_string(&self, method: &Rc<LoxFunction>) -> Result<String, LoxResult> {
+        if !method.arity().accepts(0) {
+            return Err(LoxResult::runtime_error(
+                method.name().dup(),
+                "Method 'toString' must take no arguments.",
+            ));
+        }
+        match method.call(self, Vec::new(), None)? {
+            Object::Str(string) => Ok(string),
+            _ => Err(LoxResult::runtime_error(
+                method.name().dup(),
+                "Method 'toString' must return a string.",
+            )),
+        }
+    }
+
+    // a string written back as a literal, escaping what the scanner would not read verbatim.
+    fn quote(string: &str) -> String {
+        let mut quoted = String::from('"');
+        for ch in string.chars() {
+            match ch {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\t' => quoted.push_str("\\t"),
+                '\r' => quoted.push_str("\\r"),
+                '\0' => quoted.push_str("\\0"),
+                '$' => quoted.push_str("\\$"),
+                ch => quoted.push(ch),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    pub fn is_truthy(&self, right: Object) -> bool {
         !matches!(right, Object::Nil | Object::Bool(false))
     }
 
@@ -509,6 +1288,921 @@ impl Interpreter {
         self.locals.borrow_mut().insert(expr, depth);
     }
 
+    // a method that two traits of a class both have is a conflict unless the class defines
+    // it itself, whichever trait is listed first would otherwise win by accident.
+    fn check_traits(stmt: &ClassStmt, traits: &[Rc<LoxTrait>]) -> Result<(), LoxResult> {
+        let own = stmt
+            .methods
+            .iter()
+            .filter_map(|method| match method.deref() {
+                Stmt::Function(func) => Some(func.name.as_string()),
+                _ => None,
+            })
+            .collect::<HashSet<String>>();
+        for (i, first) in traits.iter().enumerate() {
+            for second in traits[i + 1..].iter() {
+                if Rc::ptr_eq(first, second) {
+                    return Err(LoxResult::runtime_error(
+                        stmt.name.dup(),
+                        &format!("Trait '{}' is mixed in more than once.", first.name()),
+                    ));
+                }
+                let conflict = first
+                    .method_names()
+                    .into_iter()
+                    .find(|name| !own.contains(name) && second.find_method(name).is_some());
+                if let Some(name) = conflict {
+                    return Err(LoxResult::runtime_error(
+                        stmt.name.dup(),
+                        &format!(
+                            "Method '{}' of class '{}' comes from both trait '{}' and trait '{}'.",
+                            name,
+                            stmt.name.as_string(),
+                            first.name(),
+                            second.name()
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn methods(
+        &self,
+        class_name: &Token,
+        declarations: &[Rc<Stmt>],
+        allow_init: bool,
+    ) -> Result<HashMap<String, Object>, LoxResult> {
+        let mut methods = HashMap::new();
+        for method in declarations {
+            if let Stmt::Function(func) = method.deref() {
+                let is_init = allow_init && func.name.as_string() == "init";
+                let function = Object::Func(Rc::new(LoxFunction::new(
+                    func,
+                    &self.environment.borrow(),
+                    is_init,
+                )));
+                methods.insert(func.name.as_string(), function);
+            } else {
+                return Err(LoxResult::runtime_error(
+                    class_name.dup(),
+                    "Non-function method in class.",
+                ));
+            }
+        }
+        Ok(methods)
+    }
+
+    fn binary_op(
+        &self,
+        left: Object,
+        operator: &Token,
+        right: Object,
+    ) -> Result<Object, LoxResult> {
+        if let Some(result) = self.binary_special(&left, operator, &right)? {
+            return Ok(result);
+        }
+
+        // concatenating an instance with a string goes through its toString().
+        if operator.is(TokenType::Plus) {
+            match (&left, &right) {
+                (Object::Str(string), Object::Instance(_)) => {
+                    return Ok(Object::Str(format!(
+                        "{}{}",
+                        string,
+                        self.stringify(&right)?
+                    )));
+                }
+                (Object::Instance(_), Object::Str(string)) => {
+                    return Ok(Object::Str(format!("{}{}", self.stringify(&left)?, string)));
+                }
+                _ => {}
+            }
+        }
+
+        let op = operator.token_type();
+        if matches!(op, TokenType::DotDot | TokenType::DotDotEqual) {
+            return match (left, right) {
+                (Object::Int(start), Object::Int(end)) => Ok(Object::Range(Rc::new(
+                    LoxRange::new(start, end, op == TokenType::DotDotEqual),
+                ))),
+                _ => Err(LoxResult::runtime_error(
+                    operator.dup(),
+                    "Range bounds must be integers.",
+                )),
+            };
+        }
+        if op == TokenType::In {
+            return self.contains(&right, &left, operator).map(Object::Bool);
+        }
+        if op == TokenType::Is {
+            return match right {
+                Object::Class(klass) => Ok(Object::Bool(match left {
+                    Object::Instance(instance) => instance.class().is_subclass_of(&klass),
+                    _ => false,
+                })),
+                Object::Trait(t) => Ok(Object::Bool(match left {
+                    Object::Instance(instance) => instance.class().has_trait(&t),
+                    _ => false,
+                })),
+                Object::Enum(lox_enum) => Ok(Object::Bool(match left {
+                    Object::EnumValue(value) => lox_enum.has_value(&value),
+                    _ => false,
+                })),
+                Object::Variant(variant) => Ok(Object::Bool(match left {
+                    Object::EnumValue(value) => Rc::ptr_eq(&value.variant, &variant),
+                    _ => false,
+                })),
+                _ => Err(LoxResult::runtime_error(
+                    operator.dup(),
+                    "Right operand of 'is' must be a class, trait, enum or enum variant.",
+                )),
+            };
+        }
+        if matches!(op, TokenType::Equal | TokenType::BangEqual) {
+            return Ok(Object::Bool((left == right) == (op == TokenType::Equal)));
+        }
+
+        // mixing an integer with a float promotes the integer.
+        let (left, right) = match (left, right) {
+            (Object::Int(left), Object::Int(right)) => return self.int_op(left, operator, right),
+            (Object::Int(left), Object::Num(right)) => {
+                (Object::Num(left as f64), Object::Num(right))
+            }
+            (Object::Num(left), Object::Int(right)) => {
+                (Object::Num(left), Object::Num(right as f64))
+            }
+            operands => operands,
+        };
+        let result = match (left, right) {
+            (Object::Num(_), Object::Num(_))
+                if matches!(
+                    op,
+                    TokenType::Ampersand
+                        | TokenType::Pipe
+                        | TokenType::Caret
+                        | TokenType::LessLess
+                        | TokenType::GreaterGreater
+                ) =>
+            {
+                return Err(LoxResult::runtime_error(
+                    operator.dup(),
+                    "Operands of bitwise operators must be integers.",
+                ));
+            }
+            (Object::Num(_), Object::Num(right))
+                if right == 0.0
+                    && matches!(
+                        op,
+                        TokenType::Slash | TokenType::Percent | TokenType::TildeSlash
+                    ) =>
+            {
+                return Err(LoxResult::runtime_error(
+                    operator.dup(),
+                    "Division by zero.",
+                ));
+            }
+            (Object::Num(left), Object::Num(right)) => match op {
+                TokenType::Plus => Object::Num(left + right),
+                TokenType::Minus => Object::Num(left - right),
+                TokenType::Slash => Object::Num(left / right),
+                TokenType::Star => Object::Num(left * right),
+                // `%` goes with the flooring `~/`, its result takes the sign of the divisor.
+                TokenType::Percent => {
+                    let rem = left % right;
+                    if rem != 0.0 && (rem < 0.0) != (right < 0.0) {
+                        Object::Num(rem + right)
+                    } else {
+                        Object::Num(rem)
+                    }
+                }
+                TokenType::TildeSlash => Object::Num((left / right).floor()),
+                TokenType::StarStar => Object::Num(left.powf(right)),
+                TokenType::Greater => Object::Bool(left > right),
+                TokenType::GreaterEqual => Object::Bool(left >= right),
+                TokenType::Less => Object::Bool(left < right),
+                TokenType::LessEqual => Object::Bool(left <= right),
+                _ => {
+                    return Err(LoxResult::error_at(
+                        operator,
+                        "Unreachable according to num binary expression",
+                    ));
+                }
+            },
+            (Object::Str(left), Object::Str(right)) => match op {
+                TokenType::Plus => Object::Str(left + &*right),
+                TokenType::Greater => Object::Bool(left > right),
+                TokenType::GreaterEqual => Object::Bool(left >= right),
+                TokenType::Less => Object::Bool(left < right),
+                TokenType::LessEqual => Object::Bool(left <= right),
+                _ => {
+                    return Err(LoxResult::error_at(
+                        operator,
+                        "Unreachable according to string binary expression",
+                    ));
+                }
+            },
+            (Object::Str(left), right @ (Object::Num(_) | Object::Int(_))) => match op {
+                TokenType::Plus => Object::Str(format!("{}{}", left, right)),
+                _ => {
+                    return Err(LoxResult::error_at(
+                        operator,
+                        "Unreachable according to num and string binary expression",
+                    ));
+                }
+            },
+            (left @ (Object::Num(_) | Object::Int(_)), Object::Str(right)) => match op {
+                TokenType::Plus => Object::Str(format!("{}{}", left, right)),
+                _ => {
+                    return Err(LoxResult::error_at(
+                        operator,
+                        "Unreachable according to string and num binary expression",
+                    ));
+                }
+            },
+            _ => {
+                return Err(LoxResult::error_at(
+                    operator,
+                    "Both operands of the comparison expression must be of the same type",
+                ))
+            }
+        };
+
+        if result == Object::ArithmeticError {
+            Err(LoxResult::runtime_error(
+                operator.dup(),
+                "Illegal expression",
+            ))
+        } else {
+            Ok(result)
+        }
+    }
+
+    // an instance operand dispatches to its special method, or to the reflected method of
+    // the right operand, e.g. `a + b` tries a.__add__(b) and then b.__radd__(a).
+    fn binary_special(
+        &self,
+        left: &Object,
+        operator: &Token,
+        right: &Object,
+    ) -> Result<Option<Object>, LoxResult> {
+        if !matches!(left, Object::Instance(_)) && !matches!(right, Object::Instance(_)) {
+            return Ok(None);
+        }
+        let (method, reflected) = match operator.token_type() {
+            TokenType::Plus => ("__add__", "__radd__"),
+            TokenType::Minus => ("__sub__", "__rsub__"),
+            TokenType::Star => ("__mul__", "__rmul__"),
+            TokenType::Slash => ("__div__", "__rdiv__"),
+            TokenType::Percent => ("__mod__", "__rmod__"),
+            TokenType::TildeSlash => ("__floordiv__", "__rfloordiv__"),
+            TokenType::StarStar => ("__pow__", "__rpow__"),
+            TokenType::Ampersand => ("__and__", "__rand__"),
+            TokenType::Pipe => ("__or__", "__ror__"),
+            TokenType::Caret => ("__xor__", "__rxor__"),
+            TokenType::LessLess => ("__lshift__", "__rlshift__"),
+            TokenType::GreaterGreater => ("__rshift__", "__rrshift__"),
+            TokenType::Less => ("__lt__", "__gt__"),
+            TokenType::LessEqual => ("__le__", "__ge__"),
+            TokenType::Greater => ("__gt__", "__lt__"),
+            TokenType::GreaterEqual => ("__ge__", "__le__"),
+            TokenType::Equal => ("__eq__", "__eq__"),
+            TokenType::BangEqual => ("__ne__", "__ne__"),
+            _ => return Ok(None),
+        };
+
+        let mut result = None;
+        if let Object::Instance(instance) = left {
+            result = self.call_special(instance, method, operator, vec![right.clone()])?;
+        }
+        if result.is_none() {
+            if let Object::Instance(instance) = right {
+                result = self.call_special(instance, reflected, operator, vec![left.clone()])?;
+            }
+        }
+        // `equals` is the plain-named alternative to __eq__.
+        if result.is_none() && operator.is(TokenType::Equal) {
+            if let Object::Instance(instance) = left {
+                result = self.call_special(instance, "equals", operator, vec![right.clone()])?;
+            }
+            if result.is_none() {
+                if let Object::Instance(instance) = right {
+                    result = self.call_special(instance, "equals", operator, vec![left.clone()])?;
+                }
+            }
+        }
+
+        match operator.token_type() {
+            TokenType::Equal => Ok(result.map(|r| Object::Bool(self.is_truthy(r)))),
+            TokenType::BangEqual => match result {
+                Some(r) => Ok(Some(Object::Bool(self.is_truthy(r)))),
+                // without __ne__, != is the negation of __eq__.
+                None => {
+                    let equal = operator.stand_in(TokenType::Equal, "==");
+                    let result = self.binary_special(left, &equal, right)?;
+                    Ok(result.map(|r| Object::Bool(!self.is_truthy(r))))
+                }
+            },
+            _ => Ok(result),
+        }
+    }
+
+    fn bind_special(&self, instance: &Rc<LoxInstance>, name: &str) -> Option<Rc<LoxFunction>> {
+        if let Some(Object::Func(method)) = instance.class().find_method(&name.to_string()) {
+            if let Object::Func(bound) = method.bind(&Object::Instance(Rc::clone(instance))) {
+                return Some(bound);
+            }
+        }
+        None
+    }
+
+    /// calls the special method `name` of the instance, or returns None when its class does
+    /// not define it.
+    fn call_special(
+        &self,
+        instance: &Rc<LoxInstance>,
+        name: &str,
+        token: &Token,
+        arguments: Vec<Object>,
+    ) -> Result<Option<Object>, LoxResult> {
+        if let Some(method) = self.bind_special(instance, name) {
+            if !method.arity().accepts(arguments.len()) {
+                return Err(LoxResult::runtime_error(
+                    token.dup(),
+                    &format!(
+                        "Method '{}' must take {} arguments but takes {}.",
+                        name,
+                        arguments.len(),
+                        method.arity()
+                    ),
+                ));
+            }
+            Ok(Some(method.call(self, arguments, None)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn int_op(&self, left: i64, operator: &Token, right: i64) -> Result<Object, LoxResult> {
+        let op = operator.token_type();
+        if right == 0
+            && matches!(
+                op,
+                TokenType::Slash | TokenType::Percent | TokenType::TildeSlash
+            )
+        {
+            return Err(LoxResult::runtime_error(
+                operator.dup(),
+                "Division by zero.",
+            ));
+        }
+
+        let result = match op {
+            TokenType::Plus => left.checked_add(right),
+            TokenType::Minus => left.checked_sub(right),
+            TokenType::Star => left.checked_mul(right),
+            TokenType::Slash => left.checked_div(right),
+            // `%` goes with the flooring `~/`, its result takes the sign of the divisor.
+            TokenType::Percent => left.checked_rem(right).map(|rem| {
+                if rem != 0 && (rem < 0) != (right < 0) {
+                    rem + right
+                } else {
+                    rem
+                }
+            }),
+            TokenType::TildeSlash => left.checked_div(right).map(|q| {
+                if left % right != 0 && (left < 0) != (right < 0) {
+                    q - 1
+                } else {
+                    q
+                }
+            }),
+            TokenType::StarStar => {
+                if right < 0 {
+                    return Ok(Object::Num((left as f64).powf(right as f64)));
+                }
+                u32::try_from(right)
+                    .ok()
+                    .and_then(|exp| left.checked_pow(exp))
+            }
+            TokenType::Ampersand => Some(left & right),
+            TokenType::Pipe => Some(left | right),
+            TokenType::Caret => Some(left ^ right),
+            TokenType::LessLess | TokenType::GreaterGreater => {
+                if !(0..64).contains(&right) {
+                    return Err(LoxResult::runtime_error(
+                        operator.dup(),
+                        "Shift amount must be between 0 and 63.",
+                    ));
+                }
+                // bits shifted out of the sign or past it are an overflow.
+                if op == TokenType::LessLess {
+                    Some(left << right).filter(|shifted| shifted >> right == left)
+                } else {
+                    Some(left >> right)
+                }
+            }
+            TokenType::Greater => return Ok(Object::Bool(left > right)),
+            TokenType::GreaterEqual => return Ok(Object::Bool(left >= right)),
+            TokenType::Less => return Ok(Object::Bool(left < right)),
+            TokenType::LessEqual => return Ok(Object::Bool(left <= right)),
+            _ => {
+                return Err(LoxResult::error_at(
+                    operator,
+                    "Unreachable according to int binary expression",
+                ));
+            }
+        };
+
+        result
+            .map(Object::Int)
+            .ok_or_else(|| LoxResult::runtime_error(operator.dup(), "Integer overflow."))
+    }
+
+    /// reads the target of a compound assignment or increment, computes the new value and
+    /// stores it back, evaluating the target's object and index only once.
+    /// returns the old and the new value.
+    fn update_target(
+        &self,
+        target: &Rc<Expr>,
+        operator: &Token,
+        update: impl FnOnce(Object) -> Result<Object, LoxResult>,
+    ) -> Result<(Object, Object), LoxResult> {
+        match target.deref() {
+            Expr::Variable(variable) => {
+                let old = self.look_up_variable(&variable.name, target.clone())?;
+                let new = update(old.clone())?;
+                self.assign_variable(&variable.name, target.clone(), new.clone())?;
+                Ok((old, new))
+            }
+            Expr::Get(get) if get.name.is(TokenType::PrivateName) => {
+                let old = self.get_private(&get.object, &get.name)?;
+                let new = update(old.clone())?;
+                self.set_private(&get.object, &get.name, new.clone())?;
+                Ok((old, new))
+            }
+            Expr::Get(get) => {
+                let object = self.evaluate(get.object.clone())?;
+                let old = self.get_property(object.clone(), &get.name)?;
+                let new = update(old.clone())?;
+                self.set_property(object, &get.name, new.clone())?;
+                Ok((old, new))
+            }
+            Expr::Index(index_expr) => {
+                let object = self.evaluate(index_expr.object.clone())?;
+                let index = self.evaluate(index_expr.index.clone())?;
+                let old = self.get_index(object.clone(), &index_expr.bracket, index.clone())?;
+                let new = update(old.clone())?;
+                self.set_index(object, &index_expr.bracket, index, new.clone())?;
+                Ok((old, new))
+            }
+            _ => Err(LoxResult::runtime_error(
+                operator.dup(),
+                "Invalid assignment target.",
+            )),
+        }
+    }
+
+    fn assign_variable(
+        &self,
+        name: &Token,
+        expr: Rc<Expr>,
+        value: Object,
+    ) -> Result<(), LoxResult> {
+        if let Some(&distance) = self.locals.borrow().get(&expr) {
+            self.environment
+                .borrow()
+                .borrow_mut()
+                .assign_at(distance, name, value)
+        } else {
+            self.environment
+                .borrow()
+                .borrow_mut()
+                .assign_global(name, value)
+        }
+    }
+
+    // the instance behind `this` and the class whose method is running. The resolver only
+    // lets private names follow `this`, and the class environment holding `#class` directly
+    // encloses the one `this` is bound in.
+    fn private_owner(
+        &self,
+        this: &Rc<Expr>,
+        name: &Token,
+    ) -> Result<(Rc<LoxInstance>, Rc<LoxClass>), LoxResult> {
+        let distance = *self.locals.borrow().get(this).unwrap();
+        let environment = self.environment.borrow().clone();
+        let instance = match environment.borrow().get_at(distance, "this")? {
+            Object::Instance(instance) => instance,
+            _ => {
+                return Err(LoxResult::runtime_error(
+                    name.dup(),
+                    "Only instances have private members.",
+                ))
+            }
+        };
+        let owner = environment.borrow().get_at(distance + 1, "#class")?;
+        match owner {
+            Object::Class(owner) => Ok((instance, owner)),
+            _ => Err(LoxResult::runtime_error(
+                name.dup(),
+                "Cannot find the class of a private member.",
+            )),
+        }
+    }
+
+    fn get_private(&self, this: &Rc<Expr>, name: &Token) -> Result<Object, LoxResult> {
+        let (instance, owner) = self.private_owner(this, name)?;
+        if let Some(value) = instance.get_private(&owner, &name.as_string()) {
+            return Ok(value);
+        }
+        if let Some(Object::Func(method)) = owner.find_private_method(&name.as_string()) {
+            return Ok(method.bind(&Object::Instance(instance)));
+        }
+        // a member of the same name in another class of the instance is still out of reach.
+        let mut klass = Some(instance.class());
+        while let Some(other) = klass {
+            let declared = instance.get_private(&other, &name.as_string()).is_some()
+                || other.find_private_method(&name.as_string()).is_some();
+            if declared && !Rc::ptr_eq(&other, &owner) {
+                return Err(LoxResult::runtime_error(
+                    name.dup(),
+                    &format!(
+                        "Private member '{}' can only be used inside class '{}'.",
+                        name.as_string(),
+                        other.name()
+                    ),
+                ));
+            }
+            klass = other.superclass();
+        }
+        Err(LoxResult::runtime_error(
+            name.dup(),
+            &format!("Undefined private member '{}'.", name.as_string()),
+        ))
+    }
+
+    fn set_private(&self, this: &Rc<Expr>, name: &Token, value: Object) -> Result<(), LoxResult> {
+        let (instance, owner) = self.private_owner(this, name)?;
+        instance.set_private(&owner, name.as_string(), value);
+        Ok(())
+    }
+
+    fn set_property(&self, object: Object, name: &Token, value: Object) -> Result<(), LoxResult> {
+        if let Object::Instance(instance) = object {
+            if let Some(Object::Func(setter)) = instance.class().find_setter(&name.as_string()) {
+                if let Object::Func(setter) = setter.bind(&Object::Instance(Rc::clone(&instance))) {
+                    setter.call(self, vec![value], None)?;
+                }
+                return Ok(());
+            }
+            // a field stored under a getter's name could never be read back.
+            if instance.class().find_getter(&name.as_string()).is_some() {
+                return Err(LoxResult::runtime_error(
+                    name.dup(),
+                    &format!("Property '{}' has no setter.", name.as_string()),
+                ));
+            }
+            instance.set(name, value);
+            Ok(())
+        } else {
+            Err(LoxResult::runtime_error(
+                name.dup(),
+                "Only instances have fields.",
+            ))
+        }
+    }
+
+    fn get_index(
+        &self,
+        object: Object,
+        bracket: &Token,
+        index: Object,
+    ) -> Result<Object, LoxResult> {
+        match object {
+            Object::List(list) => {
+                let i = self.list_index(bracket, &index, list.len())?;
+                Ok(list.elements.borrow()[i].clone())
+            }
+            Object::Tuple(tuple) => {
+                let i = self.list_index(bracket, &index, tuple.len())?;
+                Ok(tuple.elements[i].clone())
+            }
+            Object::Instance(instance) => self
+                .call_special(&instance, "__index__", bracket, vec![index])?
+                .ok_or_else(|| {
+                    LoxResult::runtime_error(
+                        bracket.dup(),
+                        "Only lists, tuples, maps and strings can be indexed.",
+                    )
+                }),
+            Object::Str(string) => {
+                let i = self.list_index(bracket, &index, string.chars().count())?;
+                Ok(Object::Str(string.chars().nth(i).unwrap().to_string()))
+            }
+            Object::Map(map) => match self.map_find(&map, &index, bracket)? {
+                Some(i) => Ok(map.get_at(i)),
+                None => Err(LoxResult::runtime_error(
+                    bracket.dup(),
+                    &format!("Undefined key '{}'.", index),
+                )),
+            },
+            _ => Err(LoxResult::runtime_error(
+                bracket.dup(),
+                "Only lists, tuples, maps and strings can be indexed.",
+            )),
+        }
+    }
+
+    fn set_index(
+        &self,
+        object: Object,
+        bracket: &Token,
+        index: Object,
+        value: Object,
+    ) -> Result<(), LoxResult> {
+        match object {
+            Object::List(list) => {
+                let i = self.list_index(bracket, &index, list.len())?;
+                list.elements.borrow_mut()[i] = value;
+                Ok(())
+            }
+            Object::Instance(instance) => {
+                match self.call_special(&instance, "__setindex__", bracket, vec![index, value])? {
+                    Some(_) => Ok(()),
+                    None => Err(LoxResult::runtime_error(
+                        bracket.dup(),
+                        "Only lists and maps support index assignment.",
+                    )),
+                }
+            }
+            Object::Map(map) => self.map_insert(&map, index, value, bracket),
+            Object::Str(_) => Err(LoxResult::runtime_error(
+                bracket.dup(),
+                "Strings are immutable.",
+            )),
+            Object::Tuple(_) => Err(LoxResult::runtime_error(
+                bracket.dup(),
+                "Tuples are immutable.",
+            )),
+            _ => Err(LoxResult::runtime_error(
+                bracket.dup(),
+                "Only lists and maps support index assignment.",
+            )),
+        }
+    }
+
+    // map keys are looked up with the same equality as `==`, so instances defining __eq__
+    // work as keys.
+    fn map_find(
+        &self,
+        map: &LoxMap,
+        key: &Object,
+        token: &Token,
+    ) -> Result<Option<usize>, LoxResult> {
+        for (i, k) in map.keys().iter().enumerate() {
+            if self.values_equal(k, key, token)? {
+                return Ok(Some(i));
+            }
+        }
+        Ok(None)
+    }
+
+    fn map_insert(
+        &self,
+        map: &LoxMap,
+        key: Object,
+        value: Object,
+        token: &Token,
+    ) -> Result<(), LoxResult> {
+        match self.map_find(map, &key, token)? {
+            Some(i) => map.set_at(i, value),
+            None => map.push(key, value),
+        }
+        Ok(())
+    }
+
+    fn values_equal(
+        &self,
+        left: &Object,
+        right: &Object,
+        token: &Token,
+    ) -> Result<bool, LoxResult> {
+        let equal = token.stand_in(TokenType::Equal, "==");
+        match self.binary_special(left, &equal, right)? {
+            Some(result) => Ok(self.is_truthy(result)),
+            None => Ok(left == right),
+        }
+    }
+
+    // instances take part through `iterator()`, which returns an object with `hasNext()` and
+    // `next()`, or by defining those two methods themselves.
+    pub fn iterator(&self, iterable: Object, token: &Token) -> Result<LoxIterator, LoxResult> {
+        match iterable {
+            Object::List(list) => Ok(LoxIterator::List(list, 0)),
+            Object::Tuple(tuple) => Ok(LoxIterator::Tuple(tuple, 0)),
+            Object::Str(string) => Ok(LoxIterator::Chars(string.chars().collect(), 0)),
+            Object::Map(map) => Ok(LoxIterator::Keys(map.keys(), 0)),
+            Object::Generator(generator) => Ok(LoxIterator::Generator(generator)),
+            Object::Range(range) => {
+                let start = range.start;
+                Ok(LoxIterator::Range(range, Some(start)))
+            }
+            Object::Instance(instance) => {
+                let iterator = match self.call_special(&instance, "iterator", token, Vec::new())? {
+                    Some(iterator) => iterator,
+                    None => Object::Instance(instance),
+                };
+                match iterator {
+                    Object::Generator(generator) => Ok(LoxIterator::Generator(generator)),
+                    Object::Instance(iterator)
+                        if self.bind_special(&iterator, "hasNext").is_some()
+                            && self.bind_special(&iterator, "next").is_some() =>
+                    {
+                        Ok(LoxIterator::Instance(iterator))
+                    }
+                    _ => Err(LoxResult::runtime_error(
+                        token.dup(),
+                        "An iterator must have 'hasNext()' and 'next()' methods.",
+                    )),
+                }
+            }
+            _ => Err(LoxResult::runtime_error(
+                token.dup(),
+                "Can only iterate over lists, tuples, maps, ranges, strings, generators and iterators.",
+            )),
+        }
+    }
+
+    pub fn iterator_next(
+        &self,
+        iterator: &mut LoxIterator,
+        token: &Token,
+    ) -> Result<Option<Object>, LoxResult> {
+        if let LoxIterator::Generator(generator) = iterator {
+            return generator.next(self);
+        }
+        if let LoxIterator::Instance(instance) = iterator {
+            let has_next = self.call_special(instance, "hasNext", token, Vec::new())?;
+            if !has_next.is_some_and(|has_next| self.is_truthy(has_next)) {
+                return Ok(None);
+            }
+            return self.call_special(instance, "next", token, Vec::new());
+        }
+        Ok(iterator.next_builtin())
+    }
+
+    // `value in container`, instances answer through their __contains__ method.
+    fn contains(
+        &self,
+        container: &Object,
+        value: &Object,
+        operator: &Token,
+    ) -> Result<bool, LoxResult> {
+        if let Object::Instance(instance) = container {
+            if let Some(result) =
+                self.call_special(instance, "__contains__", operator, vec![value.clone()])?
+            {
+                return Ok(self.is_truthy(result));
+            }
+        }
+        match container {
+            Object::List(list) => {
+                let elements = list.elements.borrow().clone();
+                for element in elements.iter() {
+                    if self.values_equal(element, value, operator)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Object::Tuple(tuple) => {
+                for element in tuple.elements.iter() {
+                    if self.values_equal(element, value, operator)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Object::Map(map) => Ok(self.map_find(map, value, operator)?.is_some()),
+            // a generator is run up to the value it is asked about.
+            Object::Generator(generator) => {
+                while let Some(element) = generator.next(self)? {
+                    if self.values_equal(&element, value, operator)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Object::Range(range) => Ok(match value {
+                Object::Int(n) => range.contains(*n),
+                Object::Num(n) => n.fract() == 0.0 && range.contains(*n as i64),
+                _ => false,
+            }),
+            Object::Str(string) => match value {
+                Object::Str(part) => Ok(string.contains(part.as_str())),
+                _ => Err(LoxResult::runtime_error(
+                    operator.dup(),
+                    "Can only look for a string in a string.",
+                )),
+            },
+            _ => Err(LoxResult::runtime_error(
+                operator.dup(),
+                "Right operand of 'in' must be a list, tuple, map, range, string, generator or define __contains__.",
+            )),
+        }
+    }
+
+    // negative bounds count from the end and bounds past either end are clamped, so a slice
+    // is never out of range.
+    fn slice_range(bounds: [Option<i64>; 2], len: usize) -> (usize, usize) {
+        let clamp = |bound: i64| {
+            let bound = if bound < 0 { bound + len as i64 } else { bound };
+            bound.clamp(0, len as i64) as usize
+        };
+        let start = bounds[0].map_or(0, clamp);
+        let end = bounds[1].map_or(len, clamp);
+        (start, end.max(start))
+    }
+
+    // indexes and slice bounds take integers, and floats with no fraction such as `2.0`.
+    fn as_index(value: &Object) -> Option<i64> {
+        match value {
+            Object::Int(n) => Some(*n),
+            Object::Num(n) if n.fract() == 0.0 => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    fn list_index(&self, bracket: &Token, index: &Object, len: usize) -> Result<usize, LoxResult> {
+        let n = Self::as_index(index)
+            .ok_or_else(|| LoxResult::runtime_error(bracket.dup(), "Index must be an integer."))?;
+        if n < 0 || n as usize >= len {
+            Err(LoxResult::runtime_error(
+                bracket.dup(),
+                &format!("Index {} out of range for length {}.", n, len),
+            ))
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    fn get_property(&self, object: Object, name: &Token) -> Result<Object, LoxResult> {
+        match object {
+            Object::Instance(instance) => {
+                if let Some(Object::Func(getter)) = instance.class().find_getter(&name.as_string())
+                {
+                    if let Object::Func(getter) =
+                        getter.bind(&Object::Instance(Rc::clone(&instance)))
+                    {
+                        return getter.call(self, Vec::new(), None);
+                    }
+                }
+                instance.get(name, &instance)
+            }
+            Object::Class(klass) => {
+                if let Some(Object::Func(method)) = klass.find_class_method(&name.as_string()) {
+                    Ok(method.bind(&Object::Class(Rc::clone(&klass))))
+                } else {
+                    Err(LoxResult::runtime_error(
+                        name.dup(),
+                        &format!("Undefined property '{}'.", name.as_string()),
+                    ))
+                }
+            }
+            Object::Module(module) => module.get(name),
+            Object::Enum(lox_enum) => lox_enum.get(&name.lexeme).ok_or_else(|| {
+                LoxResult::runtime_error(
+                    name.dup(),
+                    &format!(
+                        "Undefined variant '{}' of enum '{}'.",
+                        name.lexeme,
+                        lox_enum.name()
+                    ),
+                )
+            }),
+            Object::EnumValue(value) => value.get(&name.lexeme).ok_or_else(|| {
+                LoxResult::runtime_error(
+                    name.dup(),
+                    &format!("Undefined field '{}' of {}.", name.lexeme, value.name()),
+                )
+            }),
+            Object::Generator(generator) if matches!(name.lexeme.as_str(), "hasNext" | "next") => {
+                Ok(Object::Native(Rc::new(LoxNative {
+                    func: Rc::new(GeneratorMethod {
+                        generator,
+                        has_next: name.lexeme == "hasNext",
+                    }),
+                })))
+            }
+            _ => Err(LoxResult::runtime_error(
+                name.dup(),
+                "Only instances have properties.",
+            )),
+        }
+    }
+
     fn look_up_variable(&self, name: &Token, expr: Rc<Expr>) -> Result<Object, LoxResult> {
         if let Some(distance) = self.locals.borrow().get(&expr) {
             self.environment
@@ -516,7 +2210,7 @@ impl Interpreter {
                 .borrow()
                 .get_at(*distance, &name.as_string())
         } else {
-            self.globals.borrow().get(name)
+            self.environment.borrow().borrow().get_global(name)
         }
     }
 }
@@ -1,7 +1,9 @@
-use crate::callable::LoxCallable;
+use crate::callable::{Arity, LoxCallable};
 use crate::error::LoxResult;
 use crate::interpreter::Interpreter;
+use crate::lox_function::LoxFunction;
 use crate::lox_instance::LoxInstance;
+use crate::lox_trait::LoxTrait;
 use crate::object::Object;
 use std::collections::HashMap;
 use std::fmt;
@@ -11,42 +13,137 @@ use std::rc::Rc;
 pub struct LoxClass {
     name: String,
     methods: HashMap<String, Object>,
+    class_methods: HashMap<String, Object>,
+    getters: HashMap<String, Object>,
+    setters: HashMap<String, Object>,
     superclass: Option<Rc<LoxClass>>,
+    traits: Vec<Rc<LoxTrait>>,
 }
 
 impl LoxClass {
     pub fn new(
         name: String,
         superclass: Option<Rc<LoxClass>>,
+        traits: Vec<Rc<LoxTrait>>,
         methods: HashMap<String, Object>,
+        class_methods: HashMap<String, Object>,
+        getters: HashMap<String, Object>,
+        setters: HashMap<String, Object>,
     ) -> Self {
         Self {
             name,
             methods,
+            class_methods,
+            getters,
+            setters,
             superclass,
+            traits,
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn superclass(&self) -> Option<Rc<LoxClass>> {
+        self.superclass.clone()
+    }
+
+    /// true when this class is `other` or inherits from it.
+    pub fn is_subclass_of(&self, other: &Rc<LoxClass>) -> bool {
+        std::ptr::eq(self, Rc::as_ptr(other))
+            || self
+                .superclass
+                .as_ref()
+                .is_some_and(|superclass| superclass.is_subclass_of(other))
+    }
+
+    /// true when this class or one of its superclasses mixes in the trait.
+    pub fn has_trait(&self, other: &Rc<LoxTrait>) -> bool {
+        self.traits.iter().any(|t| Rc::ptr_eq(t, other))
+            || self
+                .superclass
+                .as_ref()
+                .is_some_and(|superclass| superclass.has_trait(other))
+    }
+
+    /// names of the public instance methods, inherited and mixed in ones included, sorted.
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names = match &self.superclass {
+            Some(superclass) => superclass.method_names(),
+            None => Vec::new(),
+        };
+        for t in self.traits.iter() {
+            names.extend(t.method_names());
+        }
+        let public = self.methods.keys().filter(|name| !name.starts_with('#'));
+        names.extend(public.cloned());
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn initializer(&self) -> Option<Rc<LoxFunction>> {
+        match self.find_method(&"init".to_string()) {
+            Some(Object::Func(init)) => Some(init),
+            _ => None,
+        }
+    }
+
+    /// methods are looked up in the class itself, then in its traits in the order they were
+    /// listed, then in the superclass the same way. Two traits of a class never share a method
+    /// the class doesn't define itself, that is an error when the class is declared.
     pub fn find_method(&self, name: &String) -> Option<Object> {
         if let Some(obj) = self.methods.get(name).cloned() {
             Some(obj)
+        } else if let Some(obj) = self.traits.iter().find_map(|t| t.find_method(name)) {
+            Some(obj)
         } else if let Some(superclass) = self.superclass.clone() {
             superclass.find_method(name)
         } else {
             None
         }
     }
+
+    /// a `#name` method declared in this class, private methods aren't inherited.
+    pub fn find_private_method(&self, name: &str) -> Option<Object> {
+        self.methods.get(name).cloned()
+    }
+
+    pub fn find_class_method(&self, name: &String) -> Option<Object> {
+        if let Some(obj) = self.class_methods.get(name).cloned() {
+            Some(obj)
+        } else if let Some(superclass) = self.superclass.clone() {
+            superclass.find_class_method(name)
+        } else {
+            None
+        }
+    }
+
+    pub fn find_getter(&self, name: &String) -> Option<Object> {
+        if let Some(obj) = self.getters.get(name).cloned() {
+            Some(obj)
+        } else if let Some(superclass) = self.superclass.clone() {
+            superclass.find_getter(name)
+        } else {
+            None
+        }
+    }
+
+    pub fn find_setter(&self, name: &String) -> Option<Object> {
+        if let Some(obj) = self.setters.get(name).cloned() {
+            Some(obj)
+        } else if let Some(superclass) = self.superclass.clone() {
+            superclass.find_setter(name)
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Display for LoxClass {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let methods = self
-            .methods
-            .keys()
-            .cloned()
-            .collect::<Vec<String>>()
-            .join(", ");
-        write!(f, "<Class {} {{ {methods} }}>", self.name)
+        write!(f, "<class {}>", self.name)
     }
 }
 
@@ -66,11 +163,11 @@ impl LoxCallable for LoxClass {
         Ok(instance)
     }
 
-    fn arity(&self) -> usize {
+    fn arity(&self) -> Arity {
         if let Some(Object::Func(init)) = self.find_method(&"init".to_string()) {
             init.arity()
         } else {
-            0
+            Arity::exactly(0)
         }
     }
 }
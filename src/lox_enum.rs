@@ -0,0 +1,147 @@
+use crate::callable::{Arity, LoxCallable};
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+use crate::lox_class::LoxClass;
+use crate::object::Object;
+use std::fmt;
+use std::rc::Rc;
+
+/// `enum Shape { Circle(r), Rect(w, h), Empty }`. Reading a variant from the enum gives the
+/// single value of a variant without fields, or the constructor of one with fields.
+#[derive(Debug)]
+pub struct LoxEnum {
+    name: String,
+    members: Vec<(String, Object)>,
+}
+
+impl LoxEnum {
+    /// `variants` holds the name and, for variants declared with parentheses, the field
+    /// names of every variant in order.
+    pub fn new(name: String, variants: Vec<(String, Option<Vec<String>>)>) -> LoxEnum {
+        let members = variants
+            .into_iter()
+            .map(|(variant, fields)| {
+                let unit = fields.is_none();
+                let variant = Rc::new(LoxVariant {
+                    enum_name: name.clone(),
+                    name: variant,
+                    fields: fields.unwrap_or_default(),
+                });
+                let member = if unit {
+                    let value = LoxEnumValue::new(Rc::clone(&variant), Vec::new());
+                    Object::EnumValue(Rc::new(value))
+                } else {
+                    Object::Variant(Rc::clone(&variant))
+                };
+                (variant.name.clone(), member)
+            })
+            .collect();
+        LoxEnum { name, members }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get(&self, name: &str) -> Option<Object> {
+        self.members
+            .iter()
+            .find(|(member, _)| member == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// true when the value was made from one of this enum's variants.
+    pub fn has_value(&self, value: &LoxEnumValue) -> bool {
+        self.members.iter().any(|(_, member)| match member {
+            Object::EnumValue(unit) => Rc::ptr_eq(&unit.variant, &value.variant),
+            Object::Variant(variant) => Rc::ptr_eq(variant, &value.variant),
+            _ => false,
+        })
+    }
+}
+
+impl fmt::Display for LoxEnum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<enum {}>", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct LoxVariant {
+    enum_name: String,
+    name: String,
+    fields: Vec<String>,
+}
+
+impl fmt::Display for LoxVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<variant {}.{}>", self.enum_name, self.name)
+    }
+}
+
+/// a value of an enum. Values are only equal to themselves, so every variant without fields
+/// has exactly one value and `Circle(1) == Circle(1)` is false.
+#[derive(Debug)]
+pub struct LoxEnumValue {
+    pub variant: Rc<LoxVariant>,
+    pub values: Vec<Object>,
+}
+
+impl LoxEnumValue {
+    pub fn new(variant: Rc<LoxVariant>, values: Vec<Object>) -> LoxEnumValue {
+        LoxEnumValue { variant, values }
+    }
+
+    /// the name the value prints with, `Shape.Circle`.
+    pub fn name(&self) -> String {
+        format!("{}.{}", self.variant.enum_name, self.variant.name)
+    }
+
+    pub fn enum_name(&self) -> &str {
+        &self.variant.enum_name
+    }
+
+    pub fn get(&self, field: &str) -> Option<Object> {
+        self.variant
+            .fields
+            .iter()
+            .position(|name| name == field)
+            .map(|i| self.values[i].clone())
+    }
+}
+
+impl fmt::Display for LoxEnumValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.values.is_empty() && self.variant.fields.is_empty() {
+            return write!(f, "{}", self.name());
+        }
+        let values = self
+            .values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{}({})", self.name(), values)
+    }
+}
+
+/// calling a variant that has fields makes a new value of it.
+pub struct VariantConstructor {
+    pub variant: Rc<LoxVariant>,
+}
+
+impl LoxCallable for VariantConstructor {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        let value = LoxEnumValue::new(Rc::clone(&self.variant), arguments);
+        Ok(Object::EnumValue(Rc::new(value)))
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(self.variant.fields.len())
+    }
+}
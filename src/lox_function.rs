@@ -1,8 +1,11 @@
-use crate::callable::LoxCallable;
+use crate::callable::{Arity, LoxCallable};
 use crate::environment::Environment;
 use crate::error::LoxResult;
+use crate::expr::Expr;
 use crate::interpreter::Interpreter;
 use crate::lox_class::LoxClass;
+use crate::lox_generator::LoxGenerator;
+use crate::lox_list::LoxList;
 use crate::object::Object;
 use crate::stmt::*;
 use crate::token::Token;
@@ -15,9 +18,13 @@ use std::rc::Rc;
 pub struct LoxFunction {
     name: Token,
     params: Rc<Vec<Token>>,
+    defaults: Rc<Vec<Option<Rc<Expr>>>>,
+    rest: Option<Token>,
     body: Rc<Vec<Rc<Stmt>>>,
     closure: Rc<RefCell<Environment>>,
     is_initialized: bool,
+    is_generator: bool,
+    receiver: Option<Object>,
 }
 
 impl LoxFunction {
@@ -29,12 +36,91 @@ impl LoxFunction {
         LoxFunction {
             name: declaration.name.dup(),
             params: Rc::clone(&declaration.params),
+            defaults: Rc::clone(&declaration.defaults),
+            rest: declaration.rest.as_ref().map(|rest| rest.dup()),
             body: Rc::clone(&declaration.body),
             closure: Rc::clone(closure),
             is_initialized,
+            is_generator: declaration.generator,
+            receiver: None,
         }
     }
 
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+
+    pub fn params(&self) -> &[Token] {
+        &self.params
+    }
+
+    /// bound methods are equal when they bind the same declaration to the same receiver, so
+    /// `obj.method == obj.method` holds even though each access binds a new function.
+    pub fn is_same_method(&self, other: &LoxFunction) -> bool {
+        self.receiver.is_some()
+            && self.receiver == other.receiver
+            && Rc::ptr_eq(&self.body, &other.body)
+    }
+
+    /// puts named arguments in the position of their parameter. Parameters skipped over get
+    /// their default value, so the result can be passed to `call` positionally.
+    pub fn arrange_arguments(
+        &self,
+        interpreter: &Interpreter,
+        positional: Vec<Object>,
+        named: Vec<(Token, Object)>,
+        paren: &Token,
+    ) -> Result<Vec<Object>, LoxResult> {
+        let mut slots = vec![None; self.params.len()];
+        let mut extra = Vec::new();
+        for (i, argument) in positional.into_iter().enumerate() {
+            match slots.get_mut(i) {
+                Some(slot) => *slot = Some(argument),
+                None => extra.push(argument),
+            }
+        }
+
+        for (name, argument) in named {
+            let i = match self.params.iter().position(|p| p.lexeme == name.lexeme) {
+                Some(i) => i,
+                None => {
+                    return Err(LoxResult::runtime_error(
+                        name.dup(),
+                        &format!("Unknown parameter '{}'.", name.as_string()),
+                    ))
+                }
+            };
+            if slots[i].is_some() {
+                return Err(LoxResult::runtime_error(
+                    name.dup(),
+                    &format!("Parameter '{}' already has an argument.", name.as_string()),
+                ));
+            }
+            slots[i] = Some(argument);
+        }
+
+        let mut arguments = Vec::new();
+        for ((slot, param), default) in slots
+            .into_iter()
+            .zip(self.params.iter())
+            .zip(self.defaults.iter())
+        {
+            match (slot, default) {
+                (Some(argument), _) => arguments.push(argument),
+                (None, Some(default)) => arguments
+                    .push(interpreter.evaluate_in(default.clone(), Rc::clone(&self.closure))?),
+                (None, None) => {
+                    return Err(LoxResult::runtime_error(
+                        paren.dup(),
+                        &format!("Missing argument for parameter '{}'.", param.as_string()),
+                    ))
+                }
+            }
+        }
+        arguments.extend(extra);
+        Ok(arguments)
+    }
+
     pub fn bind(&self, instance: &Object) -> Object {
         let mut environment = Environment::new_with_enclosing(Rc::clone(&self.closure));
         environment.define("this".to_string(), instance.clone());
@@ -42,9 +128,13 @@ impl LoxFunction {
         Object::Func(Rc::new(LoxFunction {
             name: self.name.dup(),
             params: Rc::clone(&self.params),
+            defaults: Rc::clone(&self.defaults),
+            rest: self.rest.as_ref().map(|rest| rest.dup()),
             body: Rc::clone(&self.body),
             closure: Rc::new(RefCell::new(environment)),
             is_initialized: self.is_initialized,
+            is_generator: self.is_generator,
+            receiver: Some(instance.clone()),
         }))
     }
 }
@@ -58,8 +148,26 @@ impl LoxCallable for LoxFunction {
     ) -> Result<Object, LoxResult> {
         let mut e = Environment::new_with_enclosing(Rc::clone(&self.closure));
 
-        for (param, arg) in self.params.iter().zip(arguments.iter()) {
-            e.define(param.as_string(), arg.clone());
+        // missing trailing arguments take their defaults, evaluated now in the closure.
+        let mut arguments = arguments.into_iter();
+        for (param, default) in self.params.iter().zip(self.defaults.iter()) {
+            let value = match (arguments.next(), default) {
+                (Some(argument), _) => argument,
+                (None, Some(default)) => {
+                    interpreter.evaluate_in(default.clone(), Rc::clone(&self.closure))?
+                }
+                (None, None) => Object::Nil,
+            };
+            e.define(param.as_string(), value);
+        }
+        if let Some(rest) = &self.rest {
+            let rest_list = LoxList::new(arguments.collect());
+            e.define(rest.as_string(), Object::List(Rc::new(rest_list)));
+        }
+        // a generator function only binds its arguments, the body runs as values are asked for.
+        if self.is_generator {
+            let generator = LoxGenerator::new(Rc::clone(&self.body), e);
+            return Ok(Object::Generator(Rc::new(generator)));
         }
         match interpreter.execute_block(&self.body, e) {
             Err(LoxResult::ReturnValue { value }) => {
@@ -80,8 +188,13 @@ impl LoxCallable for LoxFunction {
         }
     }
 
-    fn arity(&self) -> usize {
-        self.params.len()
+    // parameters with a default are optional, a rest parameter takes any number of extras.
+    fn arity(&self) -> Arity {
+        let min = self.defaults.iter().filter(|d| d.is_none()).count();
+        match self.rest {
+            Some(_) => Arity::at_least(min),
+            None => Arity::range(min, self.params.len()),
+        }
     }
 }
 
@@ -91,6 +204,11 @@ impl fmt::Display for LoxFunction {
             .params
             .iter()
             .map(|p| p.as_string())
+            .chain(
+                self.rest
+                    .iter()
+                    .map(|rest| format!("...{}", rest.as_string())),
+            )
             .collect::<Vec<String>>()
             .join(", ");
 
@@ -0,0 +1,283 @@
+use crate::callable::{Arity, LoxCallable};
+use crate::environment::Environment;
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+use crate::lox_class::LoxClass;
+use crate::lox_iterator::LoxIterator;
+use crate::object::Object;
+use crate::stmt::*;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+/// the suspended body of a generator function. The interpreter runs statements by recursion,
+/// which can't stop halfway, so blocks, ifs and loops in a generator are run here on an
+/// explicit stack of frames that is kept between calls. Every other statement runs in the
+/// interpreter as usual.
+pub struct LoxGenerator {
+    frames: RefCell<Vec<Frame>>,
+    // the value `hasNext()` ran ahead to, handed out by the following `next()`.
+    buffered: RefCell<Option<Object>>,
+    running: Cell<bool>,
+}
+
+enum Frame {
+    Block {
+        statements: Rc<Vec<Rc<Stmt>>>,
+        next: usize,
+        environment: Rc<RefCell<Environment>>,
+    },
+    While {
+        stmt: Rc<WhileStmt>,
+        environment: Rc<RefCell<Environment>>,
+        started: bool,
+    },
+    ForIn {
+        stmt: Rc<ForInStmt>,
+        iterator: LoxIterator,
+        environment: Rc<RefCell<Environment>>,
+    },
+}
+
+enum Step {
+    Next,
+    Yield(Object),
+    Finish,
+}
+
+impl LoxGenerator {
+    pub fn new(body: Rc<Vec<Rc<Stmt>>>, environment: Environment) -> LoxGenerator {
+        LoxGenerator {
+            frames: RefCell::new(vec![Frame::Block {
+                statements: body,
+                next: 0,
+                environment: Rc::new(RefCell::new(environment)),
+            }]),
+            buffered: RefCell::new(None),
+            running: Cell::new(false),
+        }
+    }
+
+    pub fn has_next(&self, interpreter: &Interpreter) -> Result<bool, LoxResult> {
+        self.fill(interpreter)?;
+        Ok(self.buffered.borrow().is_some())
+    }
+
+    /// the next yielded value, None once the body has finished.
+    pub fn next(&self, interpreter: &Interpreter) -> Result<Option<Object>, LoxResult> {
+        self.fill(interpreter)?;
+        Ok(self.buffered.take())
+    }
+
+    fn fill(&self, interpreter: &Interpreter) -> Result<(), LoxResult> {
+        if self.buffered.borrow().is_some() {
+            return Ok(());
+        }
+        if self.running.replace(true) {
+            return Err(LoxResult::system_error("Generator is already running."));
+        }
+        // the frames are taken out while running, an error leaves the generator finished.
+        let mut frames = self.frames.take();
+        let result = Self::resume(interpreter, &mut frames);
+        self.running.set(false);
+        let value = result?;
+        self.frames.replace(frames);
+        self.buffered.replace(value);
+        Ok(())
+    }
+
+    // runs until the next `yield`, or until the body is done.
+    fn resume(
+        interpreter: &Interpreter,
+        frames: &mut Vec<Frame>,
+    ) -> Result<Option<Object>, LoxResult> {
+        while let Some(frame) = frames.last_mut() {
+            match frame {
+                Frame::Block {
+                    statements,
+                    next,
+                    environment,
+                } => {
+                    let Some(stmt) = statements.get(*next).cloned() else {
+                        frames.pop();
+                        continue;
+                    };
+                    *next += 1;
+                    let environment = Rc::clone(environment);
+                    match Self::step(interpreter, frames, stmt, environment)? {
+                        Step::Next => {}
+                        Step::Yield(value) => return Ok(Some(value)),
+                        Step::Finish => {
+                            frames.clear();
+                            return Ok(None);
+                        }
+                    }
+                }
+                Frame::While {
+                    stmt,
+                    environment,
+                    started,
+                } => {
+                    if *started {
+                        if let Some(increment) = stmt.increment.clone() {
+                            interpreter.evaluate_in(increment, Rc::clone(environment))?;
+                        }
+                    }
+                    *started = true;
+                    let condition =
+                        interpreter.evaluate_in(stmt.condition.clone(), Rc::clone(environment))?;
+                    if interpreter.is_truthy(condition) {
+                        let body = Self::single(stmt.body.clone(), Rc::clone(environment));
+                        frames.push(body);
+                    } else {
+                        frames.pop();
+                    }
+                }
+                Frame::ForIn {
+                    stmt,
+                    iterator,
+                    environment,
+                } => match interpreter.iterator_next(iterator, &stmt.keyword)? {
+                    Some(value) => {
+                        let mut iteration = Environment::new_with_enclosing(Rc::clone(environment));
+                        iteration.define(stmt.name.as_string(), value);
+                        let body =
+                            Self::single(stmt.body.clone(), Rc::new(RefCell::new(iteration)));
+                        frames.push(body);
+                    }
+                    None => {
+                        frames.pop();
+                    }
+                },
+            }
+        }
+        Ok(None)
+    }
+
+    fn step(
+        interpreter: &Interpreter,
+        frames: &mut Vec<Frame>,
+        stmt: Rc<Stmt>,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Step, LoxResult> {
+        match stmt.as_ref() {
+            Stmt::Block(block) => {
+                let scope = Environment::new_with_enclosing(environment);
+                frames.push(Frame::Block {
+                    statements: Rc::clone(&block.statements),
+                    next: 0,
+                    environment: Rc::new(RefCell::new(scope)),
+                });
+            }
+            Stmt::If(stmt) => {
+                let condition =
+                    interpreter.evaluate_in(stmt.condition.clone(), Rc::clone(&environment))?;
+                let branch = if interpreter.is_truthy(condition) {
+                    Some(stmt.then_branch.clone())
+                } else {
+                    stmt.else_branch.clone()
+                };
+                if let Some(branch) = branch {
+                    frames.push(Self::single(branch, environment));
+                }
+            }
+            Stmt::Match(stmt) => {
+                if let Some((body, scope)) = interpreter.match_arm(stmt, environment)? {
+                    frames.push(Self::single(body, Rc::new(RefCell::new(scope))));
+                }
+            }
+            Stmt::While(stmt) => frames.push(Frame::While {
+                stmt: Rc::clone(stmt),
+                environment,
+                started: false,
+            }),
+            Stmt::ForIn(stmt) => {
+                let iterable =
+                    interpreter.evaluate_in(stmt.iterable.clone(), Rc::clone(&environment))?;
+                let iterator = interpreter.iterator(iterable, &stmt.keyword)?;
+                frames.push(Frame::ForIn {
+                    stmt: Rc::clone(stmt),
+                    iterator,
+                    environment,
+                });
+            }
+            Stmt::Break(_) => {
+                while let Some(frame) = frames.pop() {
+                    if !matches!(frame, Frame::Block { .. }) {
+                        break;
+                    }
+                }
+            }
+            Stmt::Continue(_) => {
+                while let Some(Frame::Block { .. }) = frames.last() {
+                    frames.pop();
+                }
+            }
+            // the value of a `return` in a generator is dropped, it only ends the generator.
+            Stmt::Return(stmt) => {
+                if let Some(value) = stmt.value.clone() {
+                    interpreter.evaluate_in(value, environment)?;
+                }
+                return Ok(Step::Finish);
+            }
+            Stmt::Yield(stmt) => {
+                let value = match stmt.value.clone() {
+                    Some(value) => interpreter.evaluate_in(value, environment)?,
+                    None => Object::Nil,
+                };
+                return Ok(Step::Yield(value));
+            }
+            _ => interpreter.execute_in(stmt, environment)?,
+        }
+        Ok(Step::Next)
+    }
+
+    // a loop body or branch runs in the environment of the statement that holds it.
+    fn single(stmt: Rc<Stmt>, environment: Rc<RefCell<Environment>>) -> Frame {
+        Frame::Block {
+            statements: Rc::new(vec![stmt]),
+            next: 0,
+            environment,
+        }
+    }
+}
+
+impl fmt::Debug for LoxGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<generator>")
+    }
+}
+
+impl fmt::Display for LoxGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<generator>")
+    }
+}
+
+/// `hasNext` and `next` read from a generator object, so generators follow the same
+/// iterator protocol as instances.
+pub struct GeneratorMethod {
+    pub generator: Rc<LoxGenerator>,
+    pub has_next: bool,
+}
+
+impl LoxCallable for GeneratorMethod {
+    fn call(
+        &self,
+        interpreter: &Interpreter,
+        _arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        if self.has_next {
+            Ok(Object::Bool(self.generator.has_next(interpreter)?))
+        } else {
+            self.generator
+                .next(interpreter)?
+                .ok_or_else(|| LoxResult::system_error("Generator is exhausted."))
+        }
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(0)
+    }
+}
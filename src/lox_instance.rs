@@ -12,6 +12,9 @@ use std::rc::Rc;
 pub struct LoxInstance {
     klass: Rc<LoxClass>,
     fields: RefCell<HashMap<String, Object>>,
+    // `#name` fields, kept per declaring class so a subclass can't see or clobber the
+    // private fields of its superclass.
+    privates: RefCell<HashMap<(usize, String), Object>>,
 }
 
 impl LoxInstance {
@@ -19,9 +22,48 @@ impl LoxInstance {
         LoxInstance {
             klass: Rc::clone(&klass),
             fields: RefCell::new(HashMap::new()),
+            privates: RefCell::new(HashMap::new()),
         }
     }
 
+    pub fn class(&self) -> Rc<LoxClass> {
+        Rc::clone(&self.klass)
+    }
+
+    /// the fields sorted by name, so they print in a stable order.
+    pub fn fields(&self) -> Vec<(String, Object)> {
+        let mut fields = self
+            .fields
+            .borrow()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<(String, Object)>>();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        fields
+    }
+
+    pub fn get_field(&self, name: &str) -> Option<Object> {
+        self.fields.borrow().get(name).cloned()
+    }
+
+    pub fn set_field(&self, name: String, value: Object) {
+        self.fields.borrow_mut().insert(name, value);
+    }
+
+    pub fn delete_field(&self, name: &str) -> Option<Object> {
+        self.fields.borrow_mut().remove(name)
+    }
+
+    pub fn get_private(&self, owner: &Rc<LoxClass>, name: &str) -> Option<Object> {
+        let key = (Rc::as_ptr(owner) as usize, name.to_string());
+        self.privates.borrow().get(&key).cloned()
+    }
+
+    pub fn set_private(&self, owner: &Rc<LoxClass>, name: String, value: Object) {
+        let key = (Rc::as_ptr(owner) as usize, name);
+        self.privates.borrow_mut().insert(key, value);
+    }
+
     pub fn get(&self, name: &Token, this: &Rc<LoxInstance>) -> Result<Object, LoxResult> {
         if let Entry::Occupied(o) = self.fields.borrow_mut().entry(name.as_string()) {
             Ok(o.get().clone())
@@ -48,19 +90,10 @@ impl LoxInstance {
     }
 }
 
+// fields are left out so that cyclic instances can't recurse, the interpreter's
+// `stringify` prints them.
 impl fmt::Display for LoxInstance {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut fields = Vec::new();
-
-        for (k, v) in self.fields.borrow().iter() {
-            fields.push(format!("{k}={v}"))
-        }
-
-        write!(
-            f,
-            "<Instance of {} {{ {} }}>",
-            self.klass,
-            fields.join(", ")
-        )
+        write!(f, "<{} instance>", self.klass.name())
     }
 }
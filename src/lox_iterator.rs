@@ -0,0 +1,58 @@
+use crate::lox_generator::LoxGenerator;
+use crate::lox_instance::LoxInstance;
+use crate::lox_list::LoxList;
+use crate::lox_range::LoxRange;
+use crate::lox_tuple::LoxTuple;
+use crate::object::Object;
+use std::rc::Rc;
+
+/// where a for-in loop is in the value it walks over. Lists are read live, so elements added
+/// during the loop are visited; maps iterate over a snapshot of their keys.
+pub enum LoxIterator {
+    List(Rc<LoxList>, usize),
+    Tuple(Rc<LoxTuple>, usize),
+    Chars(Vec<char>, usize),
+    Keys(Vec<Object>, usize),
+    /// the range and its next value, None once that would overflow.
+    Range(Rc<LoxRange>, Option<i64>),
+    /// an object with `hasNext()` and `next()` methods, called by the interpreter.
+    Instance(Rc<LoxInstance>),
+    Generator(Rc<LoxGenerator>),
+}
+
+impl LoxIterator {
+    /// the next value of a built-in sequence, None when it is done or for an instance or
+    /// generator, which need the interpreter.
+    pub fn next_builtin(&mut self) -> Option<Object> {
+        match self {
+            LoxIterator::List(list, i) => {
+                let value = list.elements.borrow().get(*i).cloned();
+                *i += 1;
+                value
+            }
+            LoxIterator::Tuple(tuple, i) => {
+                let value = tuple.elements.get(*i).cloned();
+                *i += 1;
+                value
+            }
+            LoxIterator::Chars(chars, i) => {
+                let value = chars.get(*i).map(|c| Object::Str(c.to_string()));
+                *i += 1;
+                value
+            }
+            LoxIterator::Keys(keys, i) => {
+                let value = keys.get(*i).cloned();
+                *i += 1;
+                value
+            }
+            LoxIterator::Range(range, next) => match *next {
+                Some(n) if range.contains(n) => {
+                    *next = n.checked_add(1);
+                    Some(Object::Int(n))
+                }
+                _ => None,
+            },
+            LoxIterator::Instance(_) | LoxIterator::Generator(_) => None,
+        }
+    }
+}
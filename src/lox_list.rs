@@ -0,0 +1,33 @@
+use crate::object::Object;
+use std::cell::RefCell;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoxList {
+    pub elements: RefCell<Vec<Object>>,
+}
+
+impl LoxList {
+    pub fn new(elements: Vec<Object>) -> LoxList {
+        LoxList {
+            elements: RefCell::new(elements),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.borrow().len()
+    }
+}
+
+impl fmt::Display for LoxList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let elements = self
+            .elements
+            .borrow()
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "[{elements}]")
+    }
+}
@@ -0,0 +1,51 @@
+use crate::object::Object;
+use std::cell::RefCell;
+use std::fmt;
+
+/// entries keep their insertion order. Keys are compared by the interpreter, which uses the
+/// same equality as `==`, so any value can be a key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoxMap {
+    pub entries: RefCell<Vec<(Object, Object)>>,
+}
+
+impl LoxMap {
+    pub fn new() -> LoxMap {
+        LoxMap {
+            entries: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn keys(&self) -> Vec<Object> {
+        self.entries
+            .borrow()
+            .iter()
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+
+    pub fn get_at(&self, index: usize) -> Object {
+        self.entries.borrow()[index].1.clone()
+    }
+
+    pub fn set_at(&self, index: usize, value: Object) {
+        self.entries.borrow_mut()[index].1 = value;
+    }
+
+    pub fn push(&self, key: Object, value: Object) {
+        self.entries.borrow_mut().push((key, value));
+    }
+}
+
+impl fmt::Display for LoxMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let entries = self
+            .entries
+            .borrow()
+            .iter()
+            .map(|(k, v)| format!("{k}: {v}"))
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{{{entries}}}")
+    }
+}
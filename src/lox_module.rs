@@ -0,0 +1,52 @@
+use crate::environment::Environment;
+use crate::error::LoxResult;
+use crate::object::Object;
+use crate::token::Token;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoxModule {
+    name: String,
+    globals: Rc<RefCell<Environment>>,
+    exports: HashSet<String>,
+}
+
+impl LoxModule {
+    pub fn new(
+        name: String,
+        globals: Rc<RefCell<Environment>>,
+        exports: HashSet<String>,
+    ) -> LoxModule {
+        LoxModule {
+            name,
+            globals,
+            exports,
+        }
+    }
+
+    /// exported names are read from the module's globals, so later assignments inside the
+    /// module are visible to importers.
+    pub fn get(&self, name: &Token) -> Result<Object, LoxResult> {
+        if self.exports.contains(&name.as_string()) {
+            self.globals.borrow().get(name)
+        } else {
+            Err(LoxResult::runtime_error(
+                name.dup(),
+                &format!(
+                    "Module '{}' does not export '{}'.",
+                    self.name,
+                    name.as_string()
+                ),
+            ))
+        }
+    }
+}
+
+impl fmt::Display for LoxModule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
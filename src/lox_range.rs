@@ -0,0 +1,34 @@
+use std::fmt;
+
+/// an integer range, `start..end` excludes the end and `start..=end` includes it. Values are
+/// produced on demand, a range never holds its elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoxRange {
+    pub start: i64,
+    pub end: i64,
+    pub inclusive: bool,
+}
+
+impl LoxRange {
+    pub fn new(start: i64, end: i64, inclusive: bool) -> LoxRange {
+        LoxRange {
+            start,
+            end,
+            inclusive,
+        }
+    }
+
+    pub fn contains(&self, n: i64) -> bool {
+        n >= self.start && (n < self.end || (self.inclusive && n == self.end))
+    }
+}
+
+impl fmt::Display for LoxRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.inclusive {
+            write!(f, "{}..={}", self.start, self.end)
+        } else {
+            write!(f, "{}..{}", self.start, self.end)
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::object::Object;
+use std::collections::HashMap;
+use std::fmt;
+
+/// `trait Name { ... }`, methods that classes take in with `class A with Name`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoxTrait {
+    name: String,
+    methods: HashMap<String, Object>,
+}
+
+impl LoxTrait {
+    pub fn new(name: String, methods: HashMap<String, Object>) -> Self {
+        Self { name, methods }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn find_method(&self, name: &str) -> Option<Object> {
+        self.methods.get(name).cloned()
+    }
+
+    /// names of the methods, sorted.
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names = self.methods.keys().cloned().collect::<Vec<String>>();
+        names.sort();
+        names
+    }
+}
+
+impl fmt::Display for LoxTrait {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<trait {}>", self.name)
+    }
+}
@@ -0,0 +1,34 @@
+use crate::object::Object;
+use std::fmt;
+
+/// the values of `return a, b;`. A tuple can't be changed once made, so unlike lists two
+/// tuples are equal when their elements are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoxTuple {
+    pub elements: Vec<Object>,
+}
+
+impl LoxTuple {
+    pub fn new(elements: Vec<Object>) -> LoxTuple {
+        LoxTuple { elements }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+}
+
+impl fmt::Display for LoxTuple {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let elements = self
+            .elements
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>();
+        if elements.len() == 1 {
+            write!(f, "({},)", elements[0])
+        } else {
+            write!(f, "({})", elements.join(", "))
+        }
+    }
+}
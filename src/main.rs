@@ -2,6 +2,7 @@ extern crate core;
 
 use std::env::args;
 use std::io::{self, stdout, Write};
+use std::path::Path;
 use std::rc::Rc;
 
 // mod ast_printer;
@@ -11,11 +12,21 @@ mod error;
 mod expr;
 mod interpreter;
 mod lox_class;
+mod lox_enum;
 mod lox_function;
+mod lox_generator;
 mod lox_instance;
+mod lox_iterator;
+mod lox_list;
+mod lox_map;
+mod lox_module;
+mod lox_range;
+mod lox_trait;
+mod lox_tuple;
 mod native_functions;
 mod object;
 mod parser;
+mod pattern;
 mod resolver;
 mod scanner;
 mod stmt;
@@ -58,6 +69,7 @@ impl Lox {
 
     pub fn run_file(&self, path: &str) -> io::Result<()> {
         let buf = std::fs::read_to_string(path)?;
+        self.interpreter.set_script_path(Path::new(path));
         if self.run(buf).is_err() {
             std::process::exit(65);
         }
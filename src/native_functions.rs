@@ -1,8 +1,11 @@
-use crate::callable::LoxCallable;
+use crate::callable::{Arity, LoxCallable};
 use crate::error::LoxResult;
 use crate::interpreter::Interpreter;
 use crate::lox_class::LoxClass;
+use crate::lox_instance::LoxInstance;
+use crate::lox_list::LoxList;
 use crate::object::Object;
+use std::cmp::Ordering;
 use std::fmt;
 use std::rc::Rc;
 use std::time::SystemTime;
@@ -52,8 +55,360 @@ impl LoxCallable for NativeClock {
         }
     }
 
-    fn arity(&self) -> usize {
-        0
+    fn arity(&self) -> Arity {
+        Arity::exactly(0)
+    }
+}
+
+pub struct NativeRepr;
+
+impl LoxCallable for NativeRepr {
+    fn call(
+        &self,
+        interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        Ok(Object::Str(interpreter.repr(&arguments[0])?))
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+}
+
+/// the name of a value's type, or of its class for an instance.
+pub struct NativeType;
+
+impl LoxCallable for NativeType {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        let name = match &arguments[0] {
+            Object::Num(_) => "float",
+            Object::Int(_) => "int",
+            Object::Str(_) => "string",
+            Object::Bool(_) => "bool",
+            Object::Func(_) | Object::Native(_) => "function",
+            Object::Class(_) => "class",
+            Object::Trait(_) => "trait",
+            Object::Instance(instance) => {
+                return Ok(Object::Str(instance.class().name().to_string()))
+            }
+            Object::List(_) => "list",
+            Object::Map(_) => "map",
+            Object::Module(_) => "module",
+            Object::Range(_) => "range",
+            Object::Tuple(_) => "tuple",
+            Object::Generator(_) => "generator",
+            Object::Enum(_) => "enum",
+            Object::Variant(_) => "variant",
+            Object::EnumValue(value) => return Ok(Object::Str(value.enum_name().to_string())),
+            Object::Nil => "nil",
+            Object::ArithmeticError => unreachable!(),
+        };
+        Ok(Object::Str(name.to_string()))
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+}
+
+/// the class of an instance, nil for any other value.
+pub struct NativeClassOf;
+
+impl LoxCallable for NativeClassOf {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        match &arguments[0] {
+            Object::Instance(instance) => Ok(Object::Class(instance.class())),
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+}
+
+pub struct NativeSuperclassOf;
+
+impl LoxCallable for NativeSuperclassOf {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        match &arguments[0] {
+            Object::Class(klass) => Ok(klass.superclass().map_or(Object::Nil, Object::Class)),
+            _ => Err(LoxResult::system_error("superclassOf() expects a class.")),
+        }
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+}
+
+fn instance_argument(native: &str, argument: &Object) -> Result<Rc<LoxInstance>, LoxResult> {
+    match argument {
+        Object::Instance(instance) => Ok(Rc::clone(instance)),
+        _ => Err(LoxResult::system_error(&format!(
+            "{}() expects an instance.",
+            native
+        ))),
+    }
+}
+
+fn name_argument(native: &str, argument: &Object) -> Result<String, LoxResult> {
+    match argument {
+        Object::Str(name) if name.starts_with('#') => Err(LoxResult::system_error(&format!(
+            "{}() can't access private member '{}'.",
+            native, name
+        ))),
+        Object::Str(name) => Ok(name.clone()),
+        _ => Err(LoxResult::system_error(&format!(
+            "{}() expects a string field name.",
+            native
+        ))),
+    }
+}
+
+/// the field names of an instance, sorted.
+pub struct NativeFields;
+
+impl LoxCallable for NativeFields {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        let instance = instance_argument("fields", &arguments[0])?;
+        let names = instance
+            .fields()
+            .into_iter()
+            .map(|(name, _)| Object::Str(name))
+            .collect();
+        Ok(Object::List(Rc::new(LoxList::new(names))))
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+}
+
+/// the method names of a class, or of an instance's class.
+pub struct NativeMethods;
+
+impl LoxCallable for NativeMethods {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        let klass = match &arguments[0] {
+            Object::Class(klass) => Rc::clone(klass),
+            Object::Instance(instance) => instance.class(),
+            _ => {
+                return Err(LoxResult::system_error(
+                    "methods() expects a class or an instance.",
+                ))
+            }
+        };
+        let names = klass.method_names().into_iter().map(Object::Str).collect();
+        Ok(Object::List(Rc::new(LoxList::new(names))))
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+}
+
+pub struct NativeHasField;
+
+impl LoxCallable for NativeHasField {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        let instance = instance_argument("hasField", &arguments[0])?;
+        let name = name_argument("hasField", &arguments[1])?;
+        Ok(Object::Bool(instance.get_field(&name).is_some()))
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(2)
+    }
+}
+
+/// reads a field directly, getters and methods are not consulted.
+pub struct NativeGetField;
+
+impl LoxCallable for NativeGetField {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        let instance = instance_argument("getField", &arguments[0])?;
+        let name = name_argument("getField", &arguments[1])?;
+        instance
+            .get_field(&name)
+            .ok_or_else(|| LoxResult::system_error(&format!("Undefined field '{}'.", name)))
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(2)
+    }
+}
+
+/// writes a field directly without calling a setter, and returns the value.
+pub struct NativeSetField;
+
+impl LoxCallable for NativeSetField {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        let instance = instance_argument("setField", &arguments[0])?;
+        let name = name_argument("setField", &arguments[1])?;
+        instance.set_field(name, arguments[2].clone());
+        Ok(arguments[2].clone())
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(3)
+    }
+}
+
+/// removes a field, returning whether it existed.
+pub struct NativeDeleteField;
+
+impl LoxCallable for NativeDeleteField {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        let instance = instance_argument("deleteField", &arguments[0])?;
+        let name = name_argument("deleteField", &arguments[1])?;
+        Ok(Object::Bool(instance.delete_field(&name).is_some()))
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::exactly(2)
+    }
+}
+
+// the largest (or smallest) of the arguments, or of the elements of a single list argument.
+fn extreme(native: &str, arguments: Vec<Object>, wanted: Ordering) -> Result<Object, LoxResult> {
+    let values = match arguments.as_slice() {
+        [Object::List(list)] => list.elements.borrow().clone(),
+        _ => arguments,
+    };
+    let mut values = values.into_iter();
+    let mut best = values
+        .next()
+        .ok_or_else(|| LoxResult::system_error(&format!("{}() of an empty list.", native)))?;
+    for value in values {
+        match value.partial_cmp(&best) {
+            Some(ordering) if ordering == wanted => best = value,
+            Some(_) => {}
+            None => {
+                return Err(LoxResult::system_error(&format!(
+                    "{}() can only compare numbers with numbers and strings with strings.",
+                    native
+                )))
+            }
+        }
+    }
+    Ok(best)
+}
+
+pub struct NativeMax;
+
+impl LoxCallable for NativeMax {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        extreme("max", arguments, Ordering::Greater)
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::at_least(1)
+    }
+}
+
+pub struct NativeMin;
+
+impl LoxCallable for NativeMin {
+    fn call(
+        &self,
+        _interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        extreme("min", arguments, Ordering::Less)
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::at_least(1)
+    }
+}
+
+/// replaces each `{}` in the format string with the next argument, converted like `print` does.
+pub struct NativeFormat;
+
+impl LoxCallable for NativeFormat {
+    fn call(
+        &self,
+        interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        _klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        let format = match &arguments[0] {
+            Object::Str(format) => format.clone(),
+            _ => return Err(LoxResult::system_error("format() expects a format string.")),
+        };
+        let pieces = format.split("{}").collect::<Vec<&str>>();
+        if pieces.len() != arguments.len() {
+            return Err(LoxResult::system_error(&format!(
+                "format() string has {} placeholders but got {} values.",
+                pieces.len() - 1,
+                arguments.len() - 1
+            )));
+        }
+
+        let mut result = pieces[0].to_string();
+        for (value, piece) in arguments[1..].iter().zip(pieces[1..].iter()) {
+            result.push_str(&interpreter.stringify(value)?);
+            result.push_str(piece);
+        }
+        Ok(Object::Str(result))
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::at_least(1)
     }
 }
 // -----------------------------------------------------------------------------------------------------------------------------
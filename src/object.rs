@@ -1,6 +1,14 @@
 use crate::lox_class::LoxClass;
+use crate::lox_enum::{LoxEnum, LoxEnumValue, LoxVariant};
 use crate::lox_function::LoxFunction;
+use crate::lox_generator::LoxGenerator;
 use crate::lox_instance::LoxInstance;
+use crate::lox_list::LoxList;
+use crate::lox_map::LoxMap;
+use crate::lox_module::LoxModule;
+use crate::lox_range::LoxRange;
+use crate::lox_trait::LoxTrait;
+use crate::lox_tuple::LoxTuple;
 use crate::native_functions::LoxNative;
 use std::cmp::*;
 use std::fmt;
@@ -11,12 +19,23 @@ use std::rc::Rc;
 #[derive(Debug, Clone)]
 pub enum Object {
     Num(f64),
+    Int(i64),
     Str(String),
     Bool(bool),
     Func(Rc<LoxFunction>),
     Class(Rc<LoxClass>),
+    Trait(Rc<LoxTrait>),
     Instance(Rc<LoxInstance>),
     Native(Rc<LoxNative>),
+    List(Rc<LoxList>),
+    Map(Rc<LoxMap>),
+    Module(Rc<LoxModule>),
+    Range(Rc<LoxRange>),
+    Tuple(Rc<LoxTuple>),
+    Generator(Rc<LoxGenerator>),
+    Enum(Rc<LoxEnum>),
+    Variant(Rc<LoxVariant>),
+    EnumValue(Rc<LoxEnumValue>),
     Nil,
     ArithmeticError,
 }
@@ -24,7 +43,10 @@ pub enum Object {
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
+            // whole floats keep a `.0` so they print differently from ints.
+            Object::Num(n) if n.is_finite() && n.fract() == 0.0 => write!(f, "{n:.1}"),
             Object::Num(n) => write!(f, "{n}"),
+            Object::Int(n) => write!(f, "{n}"),
             Object::Str(n) => write!(f, "{n}"),
             Object::Bool(n) => {
                 if *n {
@@ -35,8 +57,18 @@ impl fmt::Display for Object {
             }
             Object::Func(n) => write!(f, "{}", n),
             Object::Class(n) => write!(f, "{}", n),
+            Object::Trait(n) => write!(f, "{}", n),
             Object::Instance(n) => write!(f, "{}", n),
             Object::Native(n) => write!(f, "{}", n),
+            Object::List(n) => write!(f, "{}", n),
+            Object::Map(n) => write!(f, "{}", n),
+            Object::Module(n) => write!(f, "{}", n),
+            Object::Range(n) => write!(f, "{}", n),
+            Object::Tuple(n) => write!(f, "{}", n),
+            Object::Generator(n) => write!(f, "{}", n),
+            Object::Enum(n) => write!(f, "{}", n),
+            Object::Variant(n) => write!(f, "{}", n),
+            Object::EnumValue(n) => write!(f, "{}", n),
             Object::Nil => write!(f, "nil"),
             Object::ArithmeticError => panic!("Should not be trying to print this object"),
         }
@@ -98,9 +130,30 @@ impl PartialEq<Self> for Object {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Object::Num(left), Object::Num(right)) => left.eq(right),
+            (Object::Int(left), Object::Int(right)) => left.eq(right),
+            (Object::Int(left), Object::Num(right)) => (*left as f64).eq(right),
+            (Object::Num(left), Object::Int(right)) => left.eq(&(*right as f64)),
             (Object::Str(left), Object::Str(right)) => left.eq(right),
             (Object::Bool(left), Object::Bool(right)) => left.eq(right),
             (Object::Nil, Object::Nil) => true,
+            // reference types are equal only to themselves.
+            (Object::Func(left), Object::Func(right)) => {
+                Rc::ptr_eq(left, right) || left.is_same_method(right)
+            }
+            (Object::Class(left), Object::Class(right)) => Rc::ptr_eq(left, right),
+            (Object::Trait(left), Object::Trait(right)) => Rc::ptr_eq(left, right),
+            (Object::Instance(left), Object::Instance(right)) => Rc::ptr_eq(left, right),
+            (Object::Native(left), Object::Native(right)) => Rc::ptr_eq(left, right),
+            (Object::List(left), Object::List(right)) => Rc::ptr_eq(left, right),
+            (Object::Map(left), Object::Map(right)) => Rc::ptr_eq(left, right),
+            (Object::Module(left), Object::Module(right)) => Rc::ptr_eq(left, right),
+            (Object::Generator(left), Object::Generator(right)) => Rc::ptr_eq(left, right),
+            (Object::Enum(left), Object::Enum(right)) => Rc::ptr_eq(left, right),
+            (Object::Variant(left), Object::Variant(right)) => Rc::ptr_eq(left, right),
+            (Object::EnumValue(left), Object::EnumValue(right)) => Rc::ptr_eq(left, right),
+            // ranges and tuples are immutable, so they compare by value.
+            (Object::Range(left), Object::Range(right)) => left == right,
+            (Object::Tuple(left), Object::Tuple(right)) => left == right,
             _ => false,
         }
     }
@@ -110,6 +163,9 @@ impl PartialOrd<Self> for Object {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
             (Object::Num(left), Object::Num(right)) => left.partial_cmp(right),
+            (Object::Int(left), Object::Int(right)) => left.partial_cmp(right),
+            (Object::Int(left), Object::Num(right)) => (*left as f64).partial_cmp(right),
+            (Object::Num(left), Object::Int(right)) => left.partial_cmp(&(*right as f64)),
             (Object::Str(left), Object::Str(right)) => left.partial_cmp(right),
             (Object::Bool(left), Object::Bool(right)) => left.partial_cmp(right),
             (Object::Nil, Object::Nil) => Some(Ordering::Equal),
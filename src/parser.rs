@@ -1,26 +1,35 @@
 use crate::error::LoxResult;
 use crate::expr::{
-    AssignExpr, BinaryExpr, CallExpr, Expr, GetExpr, GroupingExpr, LiteralExpr, LogicalExpr,
-    SetExpr, SuperExpr, ThisExpr, UnaryExpr, VariableExpr,
+    AssignExpr, BinaryExpr, CallExpr, CompoundExpr, ConditionalExpr, Expr, GetExpr, GroupingExpr,
+    IndexExpr, InterpolationExpr, ListExpr, LiteralExpr, LogicalExpr, MapExpr, OptionalChainExpr,
+    SetExpr, SetIndexExpr, SliceExpr, SuperExpr, ThisExpr, TupleExpr, UnaryExpr, UpdateExpr,
+    VariableExpr,
 };
 use crate::object::Object;
+use crate::pattern::{MatchArm, Pattern};
 use crate::stmt::*;
 use crate::token::*;
 use crate::token_type::*;
 use std::rc::Rc;
 
+// the statements of a function body, and whether it yields.
+type FunctionBody = (Rc<Vec<Rc<Stmt>>>, bool);
+
 pub struct Parser<'a> {
     tokens: &'a [Token],
     current: usize,
     has_error: bool,
+    // one entry per function being parsed, set once its body contains a `yield`.
+    yields: Vec<bool>,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &[Token]) -> Parser {
+    pub fn new(tokens: &[Token]) -> Parser<'_> {
         Parser {
             tokens,
             current: 0,
             has_error: false,
+            yields: Vec::new(),
         }
     }
 
@@ -43,10 +52,20 @@ impl<'a> Parser<'a> {
     fn declaration(&mut self) -> Result<Stmt, LoxResult> {
         let result = if self.is_match(&[TokenType::Class]) {
             self.class_declaration()
+        } else if self.is_match(&[TokenType::Trait]) {
+            self.trait_declaration()
+        } else if self.is_match(&[TokenType::Enum]) {
+            self.enum_declaration()
         } else if self.is_match(&[TokenType::Fun]) {
             self.function("function")
         } else if self.is_match(&[TokenType::Var]) {
-            self.var_declaration()
+            self.var_declaration(false)
+        } else if self.is_match(&[TokenType::Const]) {
+            self.var_declaration(true)
+        } else if self.is_match(&[TokenType::Import]) {
+            self.import_declaration()
+        } else if self.is_match(&[TokenType::Export]) {
+            self.export_declaration()
         } else {
             self.statement()
         };
@@ -69,16 +88,200 @@ impl<'a> Parser<'a> {
             None
         };
 
+        let mut traits = Vec::new();
+        if self.is_match(&[TokenType::With]) {
+            loop {
+                let name = self.consume(TokenType::Identifier, "Expect trait name.")?;
+                traits.push(Rc::new(Expr::Variable(Rc::new(VariableExpr { name }))));
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
         self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
         let mut methods = Vec::new();
+        let mut class_methods = Vec::new();
+        let mut getters = Vec::new();
+        let mut setters = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(Rc::new(self.function("method")?));
+            if self.is_match(&[TokenType::Class]) {
+                class_methods.push(Rc::new(self.function("method")?));
+            } else if self.peek().lexeme == "set" && self.check_next(TokenType::Identifier) {
+                self.advance();
+                let setter = self.function("setter")?;
+                if let Stmt::Function(func) = &setter {
+                    if func.params.len() != 1 || func.rest.is_some() {
+                        self.error(func.name.dup(), "A setter must have exactly one parameter.")
+                            .report();
+                    }
+                }
+                setters.push(Rc::new(setter));
+            } else if self.check(TokenType::Identifier) && self.check_next(TokenType::LeftBrace) {
+                getters.push(Rc::new(self.getter()?));
+            } else {
+                methods.push(Rc::new(self.function("method")?));
+            }
         }
         self.consume(TokenType::RightBrace, "Expect '}' before class body.")?;
         Ok(Stmt::Class(Rc::new(ClassStmt {
             name,
             superclass,
+            traits,
             methods: Rc::new(methods),
+            class_methods: Rc::new(class_methods),
+            getters: Rc::new(getters),
+            setters: Rc::new(setters),
+        })))
+    }
+
+    // trait Name { method() { ... } ... }, only plain methods.
+    fn trait_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect a trait name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before trait body.")?;
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if self.check(TokenType::Identifier) && self.peek().lexeme == "init" {
+                return Err(self.error(self.peek().dup(), "A trait can't have an initializer."));
+            }
+            methods.push(Rc::new(self.function("method")?));
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after trait body.")?;
+        Ok(Stmt::Trait(Rc::new(TraitStmt {
+            name,
+            methods: Rc::new(methods),
+        })))
+    }
+
+    // a getter is a method declared without a parameter list, run when the property is read.
+    fn getter(&mut self) -> Result<Stmt, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect getter name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before getter body.")?;
+        let (body, generator) = self.function_body()?;
+        Ok(Stmt::Function(Rc::new(FunctionStmt {
+            name,
+            params: Rc::new(Vec::new()),
+            defaults: Rc::new(Vec::new()),
+            rest: None,
+            body,
+            generator,
+        })))
+    }
+
+    // enum Shape { Circle(r), Rect(w, h), Empty }
+    fn enum_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect enum name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before enum variants.")?;
+        let mut variants: Vec<Token> = Vec::new();
+        let mut fields = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            let variant = self.consume(TokenType::Identifier, "Expect variant name.")?;
+            if variants.iter().any(|v| v.lexeme == variant.lexeme) {
+                return Err(self.error(variant, "Already a variant with this name in this enum."));
+            }
+            let variant_fields = if self.is_match(&[TokenType::LeftParen]) {
+                let mut names: Vec<Token> = Vec::new();
+                if !self.check(TokenType::RightParen) {
+                    loop {
+                        let field = self.consume(TokenType::Identifier, "Expect field name.")?;
+                        if names.iter().any(|n| n.lexeme == field.lexeme) {
+                            return Err(self
+                                .error(field, "Already a field with this name in this variant."));
+                        }
+                        names.push(field);
+                        if !self.is_match(&[TokenType::Comma]) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expect ')' after variant fields.")?;
+                Some(names)
+            } else {
+                None
+            };
+            variants.push(variant);
+            fields.push(variant_fields);
+            if !self.is_match(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after enum variants.")?;
+        Ok(Stmt::Enum(Rc::new(EnumStmt {
+            name,
+            variants,
+            fields,
+        })))
+    }
+
+    // import "path" as name;  or  import { a, b as c } from "path";
+    fn import_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous().dup();
+        let mut names = Vec::new();
+        let mut aliases = Vec::new();
+        let mut alias = None;
+
+        let path = if self.is_match(&[TokenType::LeftBrace]) {
+            if !self.check(TokenType::RightBrace) {
+                loop {
+                    let name = self.consume(TokenType::Identifier, "Expect imported name.")?;
+                    aliases.push(if self.is_match_word("as") {
+                        self.consume(TokenType::Identifier, "Expect name after 'as'.")?
+                    } else {
+                        name.dup()
+                    });
+                    names.push(name);
+                    if !self.is_match(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after imported names.")?;
+            if !self.is_match_word("from") {
+                return Err(self.error(self.peek().dup(), "Expect 'from' after imported names."));
+            }
+            self.consume(TokenType::String, "Expect module path.")?
+        } else {
+            let path = self.consume(TokenType::String, "Expect module path after 'import'.")?;
+            if self.is_match_word("as") {
+                alias =
+                    Some(self.consume(TokenType::Identifier, "Expect module name after 'as'.")?);
+            }
+            path
+        };
+
+        self.consume(TokenType::SemiColon, "Expect ';' after import.")?;
+        Ok(Stmt::Import(Rc::new(ImportStmt {
+            keyword,
+            path,
+            alias,
+            names,
+            aliases,
+        })))
+    }
+
+    fn export_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous().dup();
+        let declaration = if self.is_match(&[TokenType::Class]) {
+            self.class_declaration()?
+        } else if self.is_match(&[TokenType::Trait]) {
+            self.trait_declaration()?
+        } else if self.is_match(&[TokenType::Enum]) {
+            self.enum_declaration()?
+        } else if self.is_match(&[TokenType::Fun]) {
+            self.function("function")?
+        } else if self.is_match(&[TokenType::Var]) {
+            self.var_declaration(false)?
+        } else if self.is_match(&[TokenType::Const]) {
+            self.var_declaration(true)?
+        } else {
+            return Err(self.error(
+                self.peek().dup(),
+                "Expect class, trait, enum, function, variable or constant declaration after 'export'.",
+            ));
+        };
+        Ok(Stmt::Export(Rc::new(ExportStmt {
+            keyword,
+            declaration: Rc::new(declaration),
         })))
     }
 
@@ -87,16 +290,35 @@ impl<'a> Parser<'a> {
         let value = if self.check(TokenType::SemiColon) {
             None
         } else {
-            Some(Rc::new(self.expression()?))
+            Some(Rc::new(self.packed_values()?))
         };
         self.consume(TokenType::SemiColon, "Expect ';' after return value.")?;
         Ok(Stmt::Return(Rc::new(ReturnStmt { keyword, value })))
     }
 
-    fn var_declaration(&mut self) -> Result<Stmt, LoxResult> {
+    // `return a, b;` and `yield a, b;` pack their values into a tuple.
+    fn packed_values(&mut self) -> Result<Expr, LoxResult> {
+        let value = self.expression()?;
+        if !self.check(TokenType::Comma) {
+            return Ok(value);
+        }
+        let mut elements = vec![Rc::new(value)];
+        while self.is_match(&[TokenType::Comma]) {
+            elements.push(Rc::new(self.expression()?));
+        }
+        Ok(Expr::Tuple(Rc::new(TupleExpr { elements })))
+    }
+
+    // `const` declares a variable that can't be assigned again, so it needs an initializer.
+    fn var_declaration(&mut self, constant: bool) -> Result<Stmt, LoxResult> {
+        if self.is_match(&[TokenType::LeftBracket, TokenType::LeftBrace]) {
+            return self.destructure_declaration(constant);
+        }
         let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
         let initializer = if self.is_match(&[TokenType::Assign]) {
             Some(Rc::new(self.expression()?))
+        } else if constant {
+            return Err(self.error(name, "A constant must be initialized."));
         } else {
             None
         };
@@ -104,7 +326,50 @@ impl<'a> Parser<'a> {
             TokenType::SemiColon,
             "Expect ';' after variable declaration.",
         )?;
-        Ok(Stmt::Var(Rc::new(VarStmt { name, initializer })))
+        Ok(Stmt::Var(Rc::new(VarStmt {
+            name,
+            initializer,
+            constant,
+        })))
+    }
+
+    // var [a, b, ...rest] = list; or var {x, y} = point;
+    fn destructure_declaration(&mut self, constant: bool) -> Result<Stmt, LoxResult> {
+        let brace = self.previous().dup();
+        let (close, closing) = if brace.is(TokenType::LeftBracket) {
+            (TokenType::RightBracket, "]")
+        } else {
+            (TokenType::RightBrace, "}")
+        };
+        let mut names = Vec::new();
+        let mut rest = None;
+        loop {
+            if brace.is(TokenType::LeftBracket) && self.is_match(&[TokenType::DotDotDot]) {
+                rest = Some(self.consume(TokenType::Identifier, "Expect name after '...'.")?);
+                break;
+            }
+            names.push(self.consume(TokenType::Identifier, "Expect variable name.")?);
+            if !self.is_match(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(close, &format!("Expect '{closing}' after variable names."))?;
+        self.consume(
+            TokenType::Assign,
+            "Expect '=' after destructuring declaration.",
+        )?;
+        let initializer = Rc::new(self.expression()?);
+        self.consume(
+            TokenType::SemiColon,
+            "Expect ';' after variable declaration.",
+        )?;
+        Ok(Stmt::Destructure(Rc::new(DestructureStmt {
+            brace,
+            names,
+            rest,
+            initializer,
+            constant,
+        })))
     }
 
     fn while_statement(&mut self) -> Result<Stmt, LoxResult> {
@@ -116,6 +381,7 @@ impl<'a> Parser<'a> {
         Ok(Stmt::While(Rc::new(WhileStmt {
             condition,
             body: Rc::new(body),
+            increment: None,
         })))
     }
 
@@ -125,6 +391,11 @@ impl<'a> Parser<'a> {
             self.consume(TokenType::SemiColon, "Expect ';' after 'break'.")?;
             return Ok(Stmt::Break(Rc::new(BreakStmt { token })));
         }
+        if self.is_match(&[TokenType::Continue]) {
+            let token = self.previous().dup();
+            self.consume(TokenType::SemiColon, "Expect ';' after 'continue'.")?;
+            return Ok(Stmt::Continue(Rc::new(ContinueStmt { token })));
+        }
         if self.is_match(&[TokenType::For]) {
             return self.for_statement();
         }
@@ -133,6 +404,10 @@ impl<'a> Parser<'a> {
             return self.if_statement();
         }
 
+        if self.is_match(&[TokenType::Match]) {
+            return self.match_statement();
+        }
+
         if self.is_match(&[TokenType::Print]) {
             return self.print_statement();
         }
@@ -141,6 +416,10 @@ impl<'a> Parser<'a> {
             return self.return_statement();
         }
 
+        if self.is_match(&[TokenType::Yield]) {
+            return self.yield_statement();
+        }
+
         if self.is_match(&[TokenType::While]) {
             return self.while_statement();
         }
@@ -156,10 +435,19 @@ impl<'a> Parser<'a> {
 
     fn for_statement(&mut self) -> Result<Stmt, LoxResult> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+        let for_in = if self.check(TokenType::Var) {
+            self.check_ahead(2, TokenType::In)
+        } else {
+            self.check(TokenType::Identifier) && self.check_next(TokenType::In)
+        };
+        if for_in {
+            return self.for_in_statement();
+        }
+
         let initializer = if self.is_match(&[TokenType::SemiColon]) {
             None
         } else if self.is_match(&[TokenType::Var]) {
-            Some(self.var_declaration()?)
+            Some(self.var_declaration(false)?)
         } else {
             Some(self.expression_statement()?)
         };
@@ -181,22 +469,13 @@ impl<'a> Parser<'a> {
         };
 
         self.consume(TokenType::RightParen, "Expect ')' after loop condition.")?;
-        let mut body = self.statement()?;
-
-        if let Some(increment) = increment {
-            body = Stmt::Block(Rc::new(BlockStmt {
-                statements: Rc::new(vec![
-                    Rc::new(body),
-                    Rc::new(Stmt::Expression(Rc::new(ExpressionStmt {
-                        expression: increment,
-                    }))),
-                ]),
-            }));
-        }
+        let body = self.statement()?;
 
-        body = Stmt::While(Rc::new(WhileStmt {
+        // the increment is kept apart from the body so that `continue` still runs it.
+        let mut body = Stmt::While(Rc::new(WhileStmt {
             condition,
             body: Rc::new(body),
+            increment,
         }));
 
         if let Some(init) = initializer {
@@ -207,6 +486,22 @@ impl<'a> Parser<'a> {
         Ok(body)
     }
 
+    // for (x in iterable) or for (var x in iterable), both declare x for the loop only.
+    fn for_in_statement(&mut self) -> Result<Stmt, LoxResult> {
+        self.is_match(&[TokenType::Var]);
+        let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+        let keyword = self.consume(TokenType::In, "Expect 'in' after loop variable.")?;
+        let iterable = Rc::new(self.expression()?);
+        self.consume(TokenType::RightParen, "Expect ')' after for-in clause.")?;
+        let body = Rc::new(self.statement()?);
+        Ok(Stmt::ForIn(Rc::new(ForInStmt {
+            name,
+            keyword,
+            iterable,
+            body,
+        })))
+    }
+
     fn if_statement(&mut self) -> Result<Stmt, LoxResult> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
         let condition = Rc::new(self.expression()?);
@@ -225,6 +520,110 @@ impl<'a> Parser<'a> {
         })))
     }
 
+    // match (value) { case 1, 2 => ...; case Point(x, y) => ...; else => ... }
+    fn match_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous().dup();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'match'.")?;
+        let subject = Rc::new(self.expression()?);
+        self.consume(TokenType::RightParen, "Expect ')' after match value.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before match cases.")?;
+
+        let mut arms = Vec::new();
+        let mut else_branch = None;
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if else_branch.is_some() {
+                return Err(self.error(self.peek().dup(), "The 'else' case must come last."));
+            }
+            if self.is_match(&[TokenType::Else]) {
+                self.consume(TokenType::Arrow, "Expect '=>' after 'else'.")?;
+                else_branch = Some(Rc::new(self.statement()?));
+                continue;
+            }
+            self.consume(TokenType::Case, "Expect 'case' or 'else' in match.")?;
+            let mut patterns = vec![self.pattern()?];
+            while self.is_match(&[TokenType::Comma]) {
+                patterns.push(self.pattern()?);
+            }
+            self.consume(TokenType::Arrow, "Expect '=>' after case patterns.")?;
+            let body = Rc::new(self.statement()?);
+            arms.push(MatchArm { patterns, body });
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after match cases.")?;
+
+        Ok(Stmt::Match(Rc::new(MatchStmt {
+            keyword,
+            subject,
+            arms: Rc::new(arms),
+            else_branch,
+        })))
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, LoxResult> {
+        if self.is_match(&[TokenType::False]) {
+            return Ok(Pattern::Literal(Object::Bool(false)));
+        }
+        if self.is_match(&[TokenType::True]) {
+            return Ok(Pattern::Literal(Object::Bool(true)));
+        }
+        if self.is_match(&[TokenType::Nil]) {
+            return Ok(Pattern::Literal(Object::Nil));
+        }
+        if self.is_match(&[TokenType::String, TokenType::Number]) {
+            return Ok(Pattern::Literal(
+                self.previous().literal.clone().unwrap_or(Object::Nil),
+            ));
+        }
+        if self.is_match(&[TokenType::Minus]) {
+            let number = self.consume(TokenType::Number, "Expect number after '-' in pattern.")?;
+            return Ok(Pattern::Literal(match number.literal {
+                Some(Object::Int(n)) => Object::Int(-n),
+                Some(Object::Num(n)) => Object::Num(-n),
+                _ => Object::Nil,
+            }));
+        }
+
+        let name = self.consume(TokenType::Identifier, "Expect pattern.")?;
+        let mut path = Expr::Variable(Rc::new(VariableExpr { name: name.dup() }));
+        let mut dotted = false;
+        while self.is_match(&[TokenType::Dot]) {
+            let property =
+                self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+            path = Expr::Get(Rc::new(GetExpr {
+                object: Rc::new(path),
+                name: property,
+                optional: false,
+            }));
+            dotted = true;
+        }
+
+        if self.is_match(&[TokenType::LeftParen]) {
+            let paren = self.previous().dup();
+            let mut fields = Vec::new();
+            if !self.check(TokenType::RightParen) {
+                loop {
+                    fields.push(self.pattern()?);
+                    if !self.is_match(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightParen, "Expect ')' after field patterns.")?;
+            return Ok(Pattern::Class {
+                class: Rc::new(path),
+                paren,
+                fields,
+            });
+        }
+
+        if dotted {
+            Ok(Pattern::Value(Rc::new(path)))
+        } else if name.lexeme == "_" {
+            Ok(Pattern::Wildcard)
+        } else {
+            Ok(Pattern::Binding(name))
+        }
+    }
+
     fn block(&mut self) -> Result<Vec<Rc<Stmt>>, LoxResult> {
         let mut statements = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
@@ -249,21 +648,57 @@ impl<'a> Parser<'a> {
     }
 
     fn function(&mut self, kind: &str) -> Result<Stmt, LoxResult> {
-        let name = self.consume(TokenType::Identifier, &format!("Expect {kind} name."))?;
+        // methods may be private, the resolver checks where they are declared.
+        let name = if kind == "method" && self.is_match(&[TokenType::PrivateName]) {
+            self.previous().dup()
+        } else {
+            self.consume(TokenType::Identifier, &format!("Expect {kind} name."))?
+        };
         self.consume(
             TokenType::LeftParen,
             &format!("Expect '(' after {kind} name."),
         )?;
         let mut params = Vec::new();
+        let mut defaults: Vec<Option<Rc<Expr>>> = Vec::new();
+        let mut rest = None;
         if !self.check(TokenType::RightParen) {
-            params.push(self.consume(TokenType::Identifier, "Expect paramter name.")?);
-            while self.is_match(&[TokenType::Comma]) {
+            loop {
                 if params.len() >= 255 && !self.has_error {
                     self.error(self.peek().dup(), "Can`t have more than 255 parameters.");
                     self.has_error = true;
                 }
 
-                params.push(self.consume(TokenType::Identifier, "Expect paramter name.")?);
+                // `...name` collects the remaining arguments into a list.
+                if self.is_match(&[TokenType::DotDotDot]) {
+                    rest =
+                        Some(self.consume(TokenType::Identifier, "Expect rest parameter name.")?);
+                    if self.check(TokenType::Comma) {
+                        return Err(self.error(
+                            self.peek().dup(),
+                            "A rest parameter must be the last parameter.",
+                        ));
+                    }
+                    break;
+                }
+
+                let param = self.consume(TokenType::Identifier, "Expect paramter name.")?;
+                if self.is_match(&[TokenType::Assign]) {
+                    defaults.push(Some(Rc::new(self.expression()?)));
+                } else {
+                    if let Some(Some(_)) = defaults.last() {
+                        self.error(
+                            param.dup(),
+                            "A parameter without a default cannot follow one with a default.",
+                        )
+                        .report();
+                    }
+                    defaults.push(None);
+                }
+                params.push(param);
+
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
             }
         }
         self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
@@ -272,16 +707,42 @@ impl<'a> Parser<'a> {
             TokenType::LeftBrace,
             &format!("Expect '{{' after {kind} body."),
         )?;
-        let body = Rc::new(self.block()?);
+        let (body, generator) = self.function_body()?;
         Ok(Stmt::Function(Rc::new(FunctionStmt {
             name,
             params: Rc::new(params),
+            defaults: Rc::new(defaults),
+            rest,
             body,
+            generator,
         })))
     }
 
+    // a body that contains `yield` makes its function a generator.
+    fn function_body(&mut self) -> Result<FunctionBody, LoxResult> {
+        self.yields.push(false);
+        let body = self.block();
+        let generator = self.yields.pop().unwrap_or(false);
+        Ok((Rc::new(body?), generator))
+    }
+
+    fn yield_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous().dup();
+        match self.yields.last_mut() {
+            Some(yields) => *yields = true,
+            None => return Err(self.error(keyword, "Can't yield outside of a function.")),
+        }
+        let value = if self.check(TokenType::SemiColon) {
+            None
+        } else {
+            Some(Rc::new(self.packed_values()?))
+        };
+        self.consume(TokenType::SemiColon, "Expect ';' after yield value.")?;
+        Ok(Stmt::Yield(Rc::new(YieldStmt { keyword, value })))
+    }
+
     fn assignment(&mut self) -> Result<Expr, LoxResult> {
-        let expr = self.or()?;
+        let expr = self.conditional()?;
         if self.is_match(&[TokenType::Assign]) {
             let equals = self.previous().dup();
             let value = self.assignment()?;
@@ -297,8 +758,73 @@ impl<'a> Parser<'a> {
                     name: get.name.dup(),
                     value: Rc::new(value),
                 })));
+            } else if let Expr::Index(index) = expr {
+                return Ok(Expr::SetIndex(Rc::new(SetIndexExpr {
+                    object: index.object.clone(),
+                    bracket: index.bracket.dup(),
+                    index: index.index.clone(),
+                    value: Rc::new(value),
+                })));
+            } else if let Expr::OptionalChain(_) = expr {
+                return Err(self.error(equals, "Invalid assignment target."));
             }
             self.error(equals, "Invalid assignment target.");
+        } else if self.is_match(&[
+            TokenType::PlusAssign,
+            TokenType::MinusAssign,
+            TokenType::StarAssign,
+            TokenType::SlashAssign,
+            TokenType::PercentAssign,
+        ]) {
+            let operator = self.previous().dup();
+            let value = self.assignment()?;
+            if !Parser::is_assignable(&expr) {
+                return Err(self.error(operator, "Invalid assignment target."));
+            }
+            return Ok(Expr::Compound(Rc::new(CompoundExpr {
+                target: Rc::new(expr),
+                operator,
+                value: Rc::new(value),
+            })));
+        }
+        Ok(expr)
+    }
+
+    fn is_assignable(expr: &Expr) -> bool {
+        matches!(expr, Expr::Variable(_) | Expr::Get(_) | Expr::Index(_))
+    }
+
+    // the else branch recurses into conditional, so `a ? b : c ? d : e` groups to the right.
+    fn conditional(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.nullish()?;
+
+        if self.is_match(&[TokenType::Question]) {
+            let then_branch = self.expression()?;
+            self.consume(
+                TokenType::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Expr::Conditional(Rc::new(ConditionalExpr {
+                condition: Rc::new(expr),
+                then_branch: Rc::new(then_branch),
+                else_branch: Rc::new(else_branch),
+            })));
+        }
+        Ok(expr)
+    }
+
+    fn nullish(&mut self) -> Result<Expr, LoxResult> {
+        let mut expr = self.or()?;
+
+        while self.is_match(&[TokenType::QuestionQuestion]) {
+            let operator = self.previous().dup();
+            let right = self.or()?;
+            expr = Expr::Logical(Rc::new(LogicalExpr {
+                left: Rc::new(expr),
+                operator,
+                right: Rc::new(right),
+            }));
         }
         Ok(expr)
     }
@@ -353,13 +879,88 @@ impl<'a> Parser<'a> {
     }
 
     fn comparison(&mut self) -> Result<Expr, LoxResult> {
-        let mut expr = self.term()?;
+        let mut expr = self.range()?;
         while self.is_match(&[
             TokenType::Greater,
             TokenType::GreaterEqual,
             TokenType::Less,
             TokenType::LessEqual,
+            TokenType::Is,
+            TokenType::In,
         ]) {
+            let operator = self.previous().dup();
+            let right = self.range()?;
+            expr = Expr::Binary(Rc::new(BinaryExpr {
+                left: Rc::new(expr),
+                operator,
+                right: Rc::new(right),
+            }));
+        }
+        Ok(expr)
+    }
+
+    // `a..b` and `a..=b` bind looser than arithmetic, so `0..n + 1` ends at n + 1.
+    fn range(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.bit_or()?;
+        if self.is_match(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            let operator = self.previous().dup();
+            let right = self.bit_or()?;
+            return Ok(Expr::Binary(Rc::new(BinaryExpr {
+                left: Rc::new(expr),
+                operator,
+                right: Rc::new(right),
+            })));
+        }
+        Ok(expr)
+    }
+
+    // bitwise operators sit between comparison and term, from loosest to tightest:
+    // | then ^ then & then the shifts.
+    fn bit_or(&mut self) -> Result<Expr, LoxResult> {
+        let mut expr = self.bit_xor()?;
+        while self.is_match(&[TokenType::Pipe]) {
+            let operator = self.previous().dup();
+            let right = self.bit_xor()?;
+            expr = Expr::Binary(Rc::new(BinaryExpr {
+                left: Rc::new(expr),
+                operator,
+                right: Rc::new(right),
+            }));
+        }
+        Ok(expr)
+    }
+
+    fn bit_xor(&mut self) -> Result<Expr, LoxResult> {
+        let mut expr = self.bit_and()?;
+        while self.is_match(&[TokenType::Caret]) {
+            let operator = self.previous().dup();
+            let right = self.bit_and()?;
+            expr = Expr::Binary(Rc::new(BinaryExpr {
+                left: Rc::new(expr),
+                operator,
+                right: Rc::new(right),
+            }));
+        }
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> Result<Expr, LoxResult> {
+        let mut expr = self.shift()?;
+        while self.is_match(&[TokenType::Ampersand]) {
+            let operator = self.previous().dup();
+            let right = self.shift()?;
+            expr = Expr::Binary(Rc::new(BinaryExpr {
+                left: Rc::new(expr),
+                operator,
+                right: Rc::new(right),
+            }));
+        }
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> Result<Expr, LoxResult> {
+        let mut expr = self.term()?;
+        while self.is_match(&[TokenType::LessLess, TokenType::GreaterGreater]) {
             let operator = self.previous().dup();
             let right = self.term()?;
             expr = Expr::Binary(Rc::new(BinaryExpr {
@@ -387,7 +988,12 @@ impl<'a> Parser<'a> {
 
     fn factor(&mut self) -> Result<Expr, LoxResult> {
         let mut expr = self.unary()?;
-        while self.is_match(&[TokenType::Slash, TokenType::Star]) {
+        while self.is_match(&[
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::Percent,
+            TokenType::TildeSlash,
+        ]) {
             let operator = self.previous().dup();
             let right = self.unary()?;
             expr = Expr::Binary(Rc::new(BinaryExpr {
@@ -400,7 +1006,7 @@ impl<'a> Parser<'a> {
     }
 
     fn unary(&mut self) -> Result<Expr, LoxResult> {
-        if self.is_match(&[TokenType::Bang, TokenType::Minus]) {
+        if self.is_match(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
             let operator = self.previous().dup();
             let right = self.unary()?;
             return Ok(Expr::Unary(Rc::new(UnaryExpr {
@@ -409,39 +1015,144 @@ impl<'a> Parser<'a> {
             })));
         }
 
-        self.call()
+        if self.is_match(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().dup();
+            let target = self.unary()?;
+            if !Parser::is_assignable(&target) {
+                return Err(self.error(operator, "Invalid increment or decrement target."));
+            }
+            return Ok(Expr::Update(Rc::new(UpdateExpr {
+                target: Rc::new(target),
+                operator,
+                prefix: true,
+            })));
+        }
+
+        self.power()
     }
 
+    // `**` binds tighter than unary minus and groups to the right: -2 ** 2 is -4 and
+    // 2 ** 3 ** 2 is 2 ** 9.
+    fn power(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.postfix()?;
+        if self.is_match(&[TokenType::StarStar]) {
+            let operator = self.previous().dup();
+            let right = self.unary()?;
+            return Ok(Expr::Binary(Rc::new(BinaryExpr {
+                left: Rc::new(expr),
+                operator,
+                right: Rc::new(right),
+            })));
+        }
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.call()?;
+        if self.is_match(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().dup();
+            if !Parser::is_assignable(&expr) {
+                return Err(self.error(operator, "Invalid increment or decrement target."));
+            }
+            return Ok(Expr::Update(Rc::new(UpdateExpr {
+                target: Rc::new(expr),
+                operator,
+                prefix: false,
+            })));
+        }
+        Ok(expr)
+    }
+
+    // a chain with a `?.` in it is wrapped in an optional chain, so a nil before any `?.`
+    // skips the rest of the chain: `a?.b.c()` is nil when `a` is.
     fn call(&mut self) -> Result<Expr, LoxResult> {
         let mut expr = self.primary()?;
+        let mut optional_chain = false;
         loop {
             if self.is_match(&[TokenType::LeftParen]) {
                 expr = self.finish_call(Rc::new(expr))?;
-            } else if self.is_match(&[TokenType::Dot]) {
-                let name =
-                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+            } else if self.is_match(&[TokenType::Dot, TokenType::QuestionDot]) {
+                let optional = self.previous().is(TokenType::QuestionDot);
+                optional_chain |= optional;
+                let name = if self.is_match(&[TokenType::PrivateName]) {
+                    self.previous().dup()
+                } else {
+                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?
+                };
                 expr = Expr::Get(Rc::new(GetExpr {
                     object: Rc::new(expr),
                     name,
+                    optional,
                 }));
+            } else if self.is_match(&[TokenType::LeftBracket]) {
+                let bracket = self.previous().dup();
+                let start = if self.check(TokenType::Colon) {
+                    None
+                } else {
+                    Some(Rc::new(self.expression()?))
+                };
+                // `[start:end]` takes a slice, either bound can be left out.
+                if self.is_match(&[TokenType::Colon]) {
+                    let end = if self.check(TokenType::RightBracket) {
+                        None
+                    } else {
+                        Some(Rc::new(self.expression()?))
+                    };
+                    self.consume(TokenType::RightBracket, "Expect ']' after slice.")?;
+                    expr = Expr::Slice(Rc::new(SliceExpr {
+                        object: Rc::new(expr),
+                        bracket,
+                        start,
+                        end,
+                    }));
+                } else {
+                    self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                    expr = Expr::Index(Rc::new(IndexExpr {
+                        object: Rc::new(expr),
+                        bracket,
+                        index: start.unwrap(),
+                    }));
+                }
             } else {
                 break;
             }
         }
+        if optional_chain {
+            expr = Expr::OptionalChain(Rc::new(OptionalChainExpr {
+                expression: Rc::new(expr),
+            }));
+        }
         Ok(expr)
     }
 
     fn finish_call(&mut self, callee: Rc<Expr>) -> Result<Expr, LoxResult> {
         let mut arguments = Vec::new();
+        let mut names = Vec::new();
         if !self.check(TokenType::RightParen) {
-            arguments.push(Rc::new(self.expression()?));
-            while self.is_match(&[TokenType::Comma]) {
+            loop {
                 if arguments.len() >= 255 {
                     return Err(
                         self.error(self.peek().dup(), "Can`t have more than 255 arguments.")
                     );
                 }
+                // `name: value` passes the argument to the parameter with that name.
+                if self.check(TokenType::Identifier) && self.check_next(TokenType::Colon) {
+                    names.push(Some(self.advance().dup()));
+                    self.advance();
+                } else {
+                    if let Some(Some(_)) = names.last() {
+                        self.error(
+                            self.peek().dup(),
+                            "Positional argument cannot follow named arguments.",
+                        )
+                        .report();
+                    }
+                    names.push(None);
+                }
                 arguments.push(Rc::new(self.expression()?));
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
             }
         }
         let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments")?;
@@ -449,6 +1160,7 @@ impl<'a> Parser<'a> {
             callee: Rc::clone(&callee),
             paren,
             arguments,
+            names,
         })))
     }
 
@@ -475,6 +1187,10 @@ impl<'a> Parser<'a> {
             })));
         }
 
+        if self.is_match(&[TokenType::Interpolation]) {
+            return self.interpolation();
+        }
+
         if self.is_match(&[TokenType::Super]) {
             let keyword = self.previous().dup();
             self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
@@ -494,6 +1210,42 @@ impl<'a> Parser<'a> {
             })));
         }
 
+        if self.is_match(&[TokenType::LeftBracket]) {
+            let mut elements = Vec::new();
+            if !self.check(TokenType::RightBracket) {
+                loop {
+                    elements.push(Rc::new(self.expression()?));
+                    if !self.is_match(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Expr::List(Rc::new(ListExpr { elements })));
+        }
+
+        if self.is_match(&[TokenType::LeftBrace]) {
+            let brace = self.previous().dup();
+            let mut keys = Vec::new();
+            let mut values = Vec::new();
+            if !self.check(TokenType::RightBrace) {
+                loop {
+                    keys.push(Rc::new(self.expression()?));
+                    self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                    values.push(Rc::new(self.expression()?));
+                    if !self.is_match(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+            return Ok(Expr::Map(Rc::new(MapExpr {
+                brace,
+                keys,
+                values,
+            })));
+        }
+
         if self.is_match(&[TokenType::LeftParen]) {
             let expr = self.expression()?;
             self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
@@ -507,6 +1259,31 @@ impl<'a> Parser<'a> {
         ))
     }
 
+    // "a ${b} c ${d} e" arrives as Interpolation("a ") b InterpolationEnd Interpolation(" c ") d
+    // InterpolationEnd String(" e"). The end token stops a broken expression inside `${...}`
+    // from running on into the rest of the string.
+    fn interpolation(&mut self) -> Result<Expr, LoxResult> {
+        let mut parts = Vec::new();
+        loop {
+            parts.push(Rc::new(Expr::Literal(Rc::new(LiteralExpr {
+                value: self.previous().dup().literal,
+            }))));
+            parts.push(Rc::new(self.expression()?));
+            self.consume(
+                TokenType::InterpolationEnd,
+                "Expect '}' after interpolated expression.",
+            )?;
+            if !self.is_match(&[TokenType::Interpolation]) {
+                break;
+            }
+        }
+        self.consume(TokenType::String, "Expect end of string interpolation.")?;
+        parts.push(Rc::new(Expr::Literal(Rc::new(LiteralExpr {
+            value: self.previous().dup().literal,
+        }))));
+        Ok(Expr::Interpolation(Rc::new(InterpolationExpr { parts })))
+    }
+
     fn consume(&mut self, ttype: TokenType, message: &str) -> Result<Token, LoxResult> {
         if self.check(ttype) {
             Ok(self.advance().dup())
@@ -530,13 +1307,19 @@ impl<'a> Parser<'a> {
             if matches!(
                 self.peek().token_type(),
                 TokenType::Class
+                    | TokenType::Trait
+                    | TokenType::Enum
                     | TokenType::Fun
                     | TokenType::Var
+                    | TokenType::Const
                     | TokenType::For
                     | TokenType::If
+                    | TokenType::Match
                     | TokenType::While
                     | TokenType::Print
                     | TokenType::Return
+                    | TokenType::Import
+                    | TokenType::Export
             ) {
                 return;
             }
@@ -554,6 +1337,16 @@ impl<'a> Parser<'a> {
         false
     }
 
+    // matches an identifier used as a contextual keyword, like 'as' and 'from' in imports.
+    fn is_match_word(&mut self, word: &str) -> bool {
+        if self.check(TokenType::Identifier) && self.peek().lexeme == word {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
     fn check(&self, ttype: TokenType) -> bool {
         if self.is_at_end() {
             false
@@ -562,6 +1355,17 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn check_next(&self, ttype: TokenType) -> bool {
+        self.check_ahead(1, ttype)
+    }
+
+    fn check_ahead(&self, distance: usize, ttype: TokenType) -> bool {
+        match self.tokens.get(self.current + distance) {
+            Some(token) => token.is(ttype),
+            None => false,
+        }
+    }
+
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
             self.current += 1;
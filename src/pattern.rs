@@ -0,0 +1,45 @@
+use crate::expr::Expr;
+use crate::object::Object;
+use crate::stmt::Stmt;
+use crate::token::Token;
+use std::rc::Rc;
+
+/// a pattern after `case` in a match statement.
+#[derive(Debug)]
+pub enum Pattern {
+    /// `_` matches any value without binding it.
+    Wildcard,
+    /// a number, string, bool or nil, compared with `==`.
+    Literal(Object),
+    /// a plain name matches any value and binds it for the case.
+    Binding(Token),
+    /// a dotted name such as `math.pi`, its value compared with `==`.
+    Value(Rc<Expr>),
+    /// `Point(x, y)` matches instances of the class or its subclasses. The inner patterns are
+    /// matched in order against the fields named after the parameters of the class
+    /// initializer, so `init(x, y)` must store `this.x` and `this.y`. An instance missing one
+    /// of those fields is a runtime error.
+    Class {
+        class: Rc<Expr>,
+        paren: Token,
+        fields: Vec<Pattern>,
+    },
+}
+
+impl Pattern {
+    /// the names bound by the pattern, in order.
+    pub fn bindings(&self) -> Vec<Token> {
+        match self {
+            Pattern::Binding(name) => vec![name.dup()],
+            Pattern::Class { fields, .. } => fields.iter().flat_map(Pattern::bindings).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// one `case` of a match statement, the body runs when any of the patterns matches.
+#[derive(Debug)]
+pub struct MatchArm {
+    pub patterns: Vec<Pattern>,
+    pub body: Rc<Stmt>,
+}
@@ -1,16 +1,21 @@
 use crate::error::LoxResult;
 use crate::expr::{
-    AssignExpr, BinaryExpr, CallExpr, Expr, ExprVisitor, GetExpr, GroupingExpr, LiteralExpr,
-    LogicalExpr, SetExpr, SuperExpr, ThisExpr, UnaryExpr, VariableExpr,
+    AssignExpr, BinaryExpr, CallExpr, CompoundExpr, ConditionalExpr, Expr, ExprVisitor, GetExpr,
+    GroupingExpr, IndexExpr, InterpolationExpr, ListExpr, LiteralExpr, LogicalExpr, MapExpr,
+    OptionalChainExpr, SetExpr, SetIndexExpr, SliceExpr, SuperExpr, ThisExpr, TupleExpr, UnaryExpr,
+    UpdateExpr, VariableExpr,
 };
 use crate::interpreter::Interpreter;
+use crate::pattern::Pattern;
 use crate::stmt::{
-    BlockStmt, BreakStmt, ClassStmt, ExpressionStmt, FunctionStmt, IfStmt, PrintStmt, ReturnStmt,
-    Stmt, StmtVisitor, VarStmt, WhileStmt,
+    BlockStmt, BreakStmt, ClassStmt, ContinueStmt, DestructureStmt, EnumStmt, ExportStmt,
+    ExpressionStmt, ForInStmt, FunctionStmt, IfStmt, ImportStmt, MatchStmt, PrintStmt, ReturnStmt,
+    Stmt, StmtVisitor, TraitStmt, VarStmt, WhileStmt, YieldStmt,
 };
 use crate::token::Token;
+use crate::token_type::TokenType;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::rc::Rc;
 
@@ -18,6 +23,8 @@ use std::rc::Rc;
 pub struct Resolver<'a> {
     interpreter: &'a Interpreter,
     scopes: RefCell<Vec<RefCell<HashMap<String, bool>>>>,
+    // the names declared with `const`, one set per scope.
+    constants: RefCell<Vec<HashSet<String>>>,
     has_error: RefCell<bool>,
     current_fun_type: RefCell<FunctionType>,
     current_class_type: RefCell<ClassType>,
@@ -37,6 +44,7 @@ enum ClassType {
     None,
     Class,
     SubClass,
+    Trait,
 }
 
 impl<'a> StmtVisitor<()> for Resolver<'a> {
@@ -60,19 +68,27 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
                 } else {
                     self.current_class_type.replace(ClassType::SubClass);
                     self.resolve_expr(superclass.clone())?;
-                    self.begin_scope();
-                    self.scopes
-                        .borrow()
-                        .last()
-                        .unwrap()
-                        .borrow_mut()
-                        .insert("super".to_string(), true);
                 }
             } else {
                 self.error(stmt.name.dup(), "Get superclass name failed.");
             }
         }
 
+        for t in stmt.traits.iter() {
+            self.resolve_expr(t.clone())?;
+        }
+
+        // the class scope holds `super`, and at runtime the class itself for private lookups.
+        self.begin_scope();
+        if *self.current_class_type.borrow() == ClassType::SubClass {
+            self.scopes
+                .borrow()
+                .last()
+                .unwrap()
+                .borrow_mut()
+                .insert("super".to_string(), true);
+        }
+
         self.begin_scope();
         self.scopes
             .borrow()
@@ -81,6 +97,17 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
             .borrow_mut()
             .insert("this".to_string(), true);
 
+        let class_methods = stmt.class_methods.iter();
+        let accessors = stmt.getters.iter().chain(stmt.setters.iter());
+        for method in class_methods.chain(accessors) {
+            if let Stmt::Function(method) = method.deref() {
+                if method.name.is(TokenType::PrivateName) {
+                    self.error(method.name.dup(), "Only instance methods can be private.");
+                }
+                self.resolve_function(method, FunctionType::Method)?;
+            }
+        }
+
         for method in stmt.methods.deref() {
             if let Stmt::Function(method) = method.deref() {
                 let declaration = if method.name.as_string() == "init" {
@@ -98,9 +125,7 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         }
 
         self.end_scope();
-        if stmt.superclass.is_some() {
-            self.end_scope();
-        }
+        self.end_scope();
         self.current_class_type.replace(enclosing_class);
         Ok(())
     }
@@ -115,6 +140,57 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_continue_stmt(
+        &self,
+        _wrapper: Rc<Stmt>,
+        stmt: &ContinueStmt,
+    ) -> Result<(), LoxResult> {
+        if !*self.in_while.borrow() {
+            self.error(
+                stmt.token.dup(),
+                "Continue statement outside of a for/while loop",
+            )
+        }
+        Ok(())
+    }
+
+    fn visit_destructure_stmt(
+        &self,
+        _wrapper: Rc<Stmt>,
+        stmt: &DestructureStmt,
+    ) -> Result<(), LoxResult> {
+        let names = stmt.names.iter().chain(stmt.rest.iter());
+        for name in names.clone() {
+            self.declare(name);
+        }
+        self.resolve_expr(stmt.initializer.clone())?;
+        for name in names {
+            self.define(name);
+            if stmt.constant {
+                if let Some(constants) = self.constants.borrow_mut().last_mut() {
+                    constants.insert(name.as_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_enum_stmt(&self, _wrapper: Rc<Stmt>, stmt: &EnumStmt) -> Result<(), LoxResult> {
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        Ok(())
+    }
+
+    fn visit_export_stmt(&self, _wrapper: Rc<Stmt>, stmt: &ExportStmt) -> Result<(), LoxResult> {
+        if !self.scopes.borrow().is_empty() {
+            self.error(
+                stmt.keyword.dup(),
+                "Can only export top-level declarations.",
+            );
+        }
+        self.resolve_stmt(stmt.declaration.clone())
+    }
+
     fn visit_expression_stmt(
         &self,
         _wrapper: Rc<Stmt>,
@@ -124,6 +200,18 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_forin_stmt(&self, _wrapper: Rc<Stmt>, stmt: &ForInStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(stmt.iterable.clone())?;
+        let previous_nesting = self.in_while.replace(true);
+        self.begin_scope();
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        self.resolve_stmt(stmt.body.clone())?;
+        self.end_scope();
+        self.in_while.replace(previous_nesting);
+        Ok(())
+    }
+
     fn visit_function_stmt(
         &self,
         _wrapper: Rc<Stmt>,
@@ -144,6 +232,60 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_import_stmt(&self, _wrapper: Rc<Stmt>, stmt: &ImportStmt) -> Result<(), LoxResult> {
+        if let Some(alias) = &stmt.alias {
+            self.declare(alias);
+            self.define(alias);
+        }
+        for alias in stmt.aliases.iter() {
+            self.declare(alias);
+            self.define(alias);
+        }
+        Ok(())
+    }
+
+    // each case gets its own scope, the names its patterns bind can't be seen by other cases
+    // or after the match.
+    fn visit_match_stmt(&self, _wrapper: Rc<Stmt>, stmt: &MatchStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(stmt.subject.clone())?;
+        for arm in stmt.arms.iter() {
+            for pattern in arm.patterns.iter() {
+                self.resolve_pattern(pattern)?;
+            }
+
+            let bindings = arm.patterns[0].bindings();
+            let names = |bindings: &[Token]| {
+                let mut names: Vec<String> = bindings.iter().map(|b| b.as_string()).collect();
+                names.sort();
+                names
+            };
+            for pattern in arm.patterns[1..].iter() {
+                let other = pattern.bindings();
+                if names(&other) != names(&bindings) {
+                    let token = other.first().or(bindings.first()).unwrap_or(&stmt.keyword);
+                    self.error(
+                        token.dup(),
+                        "Every pattern of a case must bind the same names.",
+                    );
+                }
+            }
+
+            self.begin_scope();
+            for name in bindings.iter() {
+                self.declare(name);
+                self.define(name);
+            }
+            self.resolve_stmt(arm.body.clone())?;
+            self.end_scope();
+        }
+        if let Some(else_branch) = stmt.else_branch.clone() {
+            self.begin_scope();
+            self.resolve_stmt(else_branch)?;
+            self.end_scope();
+        }
+        Ok(())
+    }
+
     fn visit_print_stmt(&self, _wrapper: Rc<Stmt>, stmt: &PrintStmt) -> Result<(), LoxResult> {
         self.resolve_expr(stmt.expression.clone())?;
         Ok(())
@@ -165,12 +307,53 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_yield_stmt(&self, _wrapper: Rc<Stmt>, stmt: &YieldStmt) -> Result<(), LoxResult> {
+        if *self.current_fun_type.borrow() == FunctionType::Initializer {
+            self.error(stmt.keyword.dup(), "Can't yield from an initializer.")
+        }
+        if let Some(value) = stmt.value.clone() {
+            self.resolve_expr(value)?;
+        }
+        Ok(())
+    }
+
+    fn visit_trait_stmt(&self, _wrapper: Rc<Stmt>, stmt: &TraitStmt) -> Result<(), LoxResult> {
+        let enclosing_class = self.current_class_type.replace(ClassType::Trait);
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+
+        self.begin_scope();
+        self.scopes
+            .borrow()
+            .last()
+            .unwrap()
+            .borrow_mut()
+            .insert("this".to_string(), true);
+        for method in stmt.methods.iter() {
+            if let Stmt::Function(method) = method.deref() {
+                if method.name.is(TokenType::PrivateName) {
+                    self.error(method.name.dup(), "Trait methods can't be private.");
+                }
+                self.resolve_function(method, FunctionType::Method)?;
+            }
+        }
+        self.end_scope();
+
+        self.current_class_type.replace(enclosing_class);
+        Ok(())
+    }
+
     fn visit_var_stmt(&self, _wrapper: Rc<Stmt>, stmt: &VarStmt) -> Result<(), LoxResult> {
         self.declare(&stmt.name);
         if let Some(init) = stmt.initializer.clone() {
             self.resolve_expr(init)?;
         }
         self.define(&stmt.name);
+        if stmt.constant {
+            if let Some(constants) = self.constants.borrow_mut().last_mut() {
+                constants.insert(stmt.name.as_string());
+            }
+        }
         Ok(())
     }
 
@@ -178,6 +361,9 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         let previous_nesting = self.in_while.replace(true);
         self.resolve_expr(stmt.condition.clone())?;
         self.resolve_stmt(stmt.body.clone())?;
+        if let Some(increment) = stmt.increment.clone() {
+            self.resolve_expr(increment)?;
+        }
         self.in_while.replace(previous_nesting);
         Ok(())
     }
@@ -186,6 +372,7 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
 impl<'a> ExprVisitor<()> for Resolver<'a> {
     fn visit_assign_expr(&self, wrapper: Rc<Expr>, expr: &AssignExpr) -> Result<(), LoxResult> {
         self.resolve_expr(expr.value.clone())?;
+        self.check_assignable(&expr.name);
         self.resolve_local(wrapper, &expr.name);
         Ok(())
     }
@@ -204,8 +391,33 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_compound_expr(
+        &self,
+        _wrapper: Rc<Expr>,
+        expr: &CompoundExpr,
+    ) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.value.clone())?;
+        if let Expr::Variable(variable) = expr.target.deref() {
+            self.check_assignable(&variable.name);
+        }
+        self.resolve_expr(expr.target.clone())?;
+        Ok(())
+    }
+
+    fn visit_conditional_expr(
+        &self,
+        _wrapper: Rc<Expr>,
+        expr: &ConditionalExpr,
+    ) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.condition.clone())?;
+        self.resolve_expr(expr.then_branch.clone())?;
+        self.resolve_expr(expr.else_branch.clone())?;
+        Ok(())
+    }
+
     fn visit_get_expr(&self, _wrapper: Rc<Expr>, expr: &GetExpr) -> Result<(), LoxResult> {
         self.resolve_expr(expr.object.clone())?;
+        self.check_private(&expr.object, &expr.name);
         Ok(())
     }
 
@@ -218,6 +430,45 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_index_expr(&self, _wrapper: Rc<Expr>, expr: &IndexExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.object.clone())?;
+        self.resolve_expr(expr.index.clone())?;
+        Ok(())
+    }
+
+    fn visit_slice_expr(&self, _wrapper: Rc<Expr>, expr: &SliceExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.object.clone())?;
+        for bound in expr.start.iter().chain(expr.end.iter()) {
+            self.resolve_expr(bound.clone())?;
+        }
+        Ok(())
+    }
+
+    fn visit_interpolation_expr(
+        &self,
+        _wrapper: Rc<Expr>,
+        expr: &InterpolationExpr,
+    ) -> Result<(), LoxResult> {
+        for part in expr.parts.iter() {
+            self.resolve_expr(part.clone())?;
+        }
+        Ok(())
+    }
+
+    fn visit_list_expr(&self, _wrapper: Rc<Expr>, expr: &ListExpr) -> Result<(), LoxResult> {
+        for element in expr.elements.iter() {
+            self.resolve_expr(element.clone())?;
+        }
+        Ok(())
+    }
+
+    fn visit_tuple_expr(&self, _wrapper: Rc<Expr>, expr: &TupleExpr) -> Result<(), LoxResult> {
+        for element in expr.elements.iter() {
+            self.resolve_expr(element.clone())?;
+        }
+        Ok(())
+    }
+
     fn visit_literal_expr(&self, _wrapper: Rc<Expr>, _expr: &LiteralExpr) -> Result<(), LoxResult> {
         Ok(())
     }
@@ -228,15 +479,46 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_map_expr(&self, _wrapper: Rc<Expr>, expr: &MapExpr) -> Result<(), LoxResult> {
+        for (key, value) in expr.keys.iter().zip(expr.values.iter()) {
+            self.resolve_expr(key.clone())?;
+            self.resolve_expr(value.clone())?;
+        }
+        Ok(())
+    }
+
+    fn visit_optionalchain_expr(
+        &self,
+        _wrapper: Rc<Expr>,
+        expr: &OptionalChainExpr,
+    ) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.expression.clone())?;
+        Ok(())
+    }
+
     fn visit_set_expr(&self, _wrapper: Rc<Expr>, expr: &SetExpr) -> Result<(), LoxResult> {
         self.resolve_expr(expr.value.clone())?;
         self.resolve_expr(expr.object.clone())?;
+        self.check_private(&expr.object, &expr.name);
+        Ok(())
+    }
+
+    fn visit_setindex_expr(
+        &self,
+        _wrapper: Rc<Expr>,
+        expr: &SetIndexExpr,
+    ) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.value.clone())?;
+        self.resolve_expr(expr.object.clone())?;
+        self.resolve_expr(expr.index.clone())?;
         Ok(())
     }
 
     fn visit_super_expr(&self, wrapper: Rc<Expr>, expr: &SuperExpr) -> Result<(), LoxResult> {
         if self.current_class_type.borrow().clone() == ClassType::None {
             self.error(expr.keyword.dup(), "Can`t use super outside of a class.");
+        } else if self.current_class_type.borrow().clone() == ClassType::Trait {
+            self.error(expr.keyword.dup(), "Can't use 'super' in a trait.");
         } else if self.current_class_type.borrow().clone() != ClassType::SubClass {
             self.error(
                 expr.keyword.dup(),
@@ -260,6 +542,14 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_update_expr(&self, _wrapper: Rc<Expr>, expr: &UpdateExpr) -> Result<(), LoxResult> {
+        if let Expr::Variable(variable) = expr.target.deref() {
+            self.check_assignable(&variable.name);
+        }
+        self.resolve_expr(expr.target.clone())?;
+        Ok(())
+    }
+
     fn visit_variable_expr(&self, wrapper: Rc<Expr>, expr: &VariableExpr) -> Result<(), LoxResult> {
         if !self.scopes.borrow().is_empty()
             && self
@@ -287,6 +577,7 @@ impl<'a> Resolver<'a> {
         Self {
             interpreter,
             scopes: RefCell::new(Vec::new()),
+            constants: RefCell::new(Vec::new()),
             has_error: RefCell::new(false),
             current_fun_type: RefCell::new(FunctionType::None),
             current_class_type: RefCell::new(ClassType::None),
@@ -314,25 +605,49 @@ impl<'a> Resolver<'a> {
         function: &FunctionStmt,
         func_type: FunctionType,
     ) -> Result<(), LoxResult> {
+        // defaults are evaluated in the function's closure, they can't see the parameters.
+        for default in function.defaults.iter().flatten() {
+            self.resolve_expr(default.clone())?;
+        }
+
         let enclosing_func = self.current_fun_type.replace(func_type);
+        let enclosing_loop = self.in_while.replace(false);
         self.begin_scope();
-        for param in function.params.iter() {
+        for param in function.params.iter().chain(function.rest.iter()) {
             self.declare(param);
             self.define(param);
         }
 
         self.resolve(&function.body)?;
         self.end_scope();
+        self.in_while.replace(enclosing_loop);
         self.current_fun_type.replace(enclosing_func);
         Ok(())
     }
 
+    // class and value patterns hold expressions, evaluated outside the scope of the case.
+    fn resolve_pattern(&self, pattern: &Pattern) -> Result<(), LoxResult> {
+        match pattern {
+            Pattern::Value(expr) => self.resolve_expr(expr.clone()),
+            Pattern::Class { class, fields, .. } => {
+                self.resolve_expr(class.clone())?;
+                for field in fields.iter() {
+                    self.resolve_pattern(field)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn begin_scope(&self) {
-        self.scopes.borrow_mut().push(RefCell::new(HashMap::new()))
+        self.scopes.borrow_mut().push(RefCell::new(HashMap::new()));
+        self.constants.borrow_mut().push(HashSet::new());
     }
 
     fn end_scope(&self) {
         self.scopes.borrow_mut().pop();
+        self.constants.borrow_mut().pop();
     }
 
     fn declare(&self, name: &Token) {
@@ -353,6 +668,44 @@ impl<'a> Resolver<'a> {
         }
     }
 
+    // `#name` members are only reachable as `this.#name` in the instance methods of a class,
+    // the interpreter then finds the member in the class whose method is running.
+    fn check_private(&self, object: &Expr, name: &Token) {
+        if !name.is(TokenType::PrivateName) {
+            return;
+        }
+        let in_class = matches!(
+            *self.current_class_type.borrow(),
+            ClassType::Class | ClassType::SubClass
+        );
+        if !in_class || !matches!(object, Expr::This(_)) {
+            self.error(
+                name.dup(),
+                &format!(
+                    "Private member '{}' can only be used through 'this' inside its class.",
+                    name.as_string()
+                ),
+            );
+        }
+    }
+
+    // constants in local scopes are rejected here, global ones when the assignment runs.
+    fn check_assignable(&self, name: &Token) {
+        let scopes = self.scopes.borrow();
+        let constants = self.constants.borrow();
+        for (scope, constants) in scopes.iter().rev().zip(constants.iter().rev()) {
+            if scope.borrow().contains_key(&name.as_string()) {
+                if constants.contains(&name.as_string()) {
+                    self.error(
+                        name.dup(),
+                        &format!("Cannot assign to constant '{}'.", name.as_string()),
+                    );
+                }
+                return;
+            }
+        }
+    }
+
     fn resolve_local(&self, expr: Rc<Expr>, name: &Token) {
         for (scope, map) in self.scopes.borrow().iter().rev().enumerate() {
             if map.borrow().contains_key(&name.as_string()) {
@@ -2,6 +2,7 @@ use crate::error::LoxResult;
 use crate::object::Object;
 use crate::token::*;
 use crate::token_type::*;
+use std::rc::Rc;
 
 pub struct Scanner {
     source: Vec<char>,
@@ -9,6 +10,19 @@ pub struct Scanner {
     start: usize,
     current: usize,
     line: usize,
+    // brace depth of every `${` interpolation we are currently inside of, and the kind of
+    // string to go on with once it is closed.
+    interpolations: Vec<(usize, StringKind)>,
+    // the path of the module being scanned, None for the main script.
+    module: Option<Rc<str>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StringKind {
+    raw: bool,
+    triple: bool,
+    // whitespace stripped from the start of every line of a triple-quoted string.
+    indent: usize,
 }
 
 impl Scanner {
@@ -19,9 +33,18 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            interpolations: Vec::new(),
+            module: None,
         }
     }
 
+    /// a scanner for an imported module, its tokens and errors carry the module's path.
+    pub fn new_in_module(source: String, module: Rc<str>) -> Scanner {
+        let mut scanner = Scanner::new(source);
+        scanner.module = Some(module);
+        scanner
+    }
+
     pub fn scan_tokens(&mut self) -> Result<&Vec<Token>, LoxResult> {
         let mut had_error: Option<LoxResult> = None;
         while !self.is_at_end() {
@@ -29,12 +52,21 @@ impl Scanner {
             match self.scan_token() {
                 Ok(_) => {}
                 Err(e) => {
+                    let e = e.in_module(&self.module);
                     e.report();
                     had_error = Some(e);
                 }
             }
         }
-        self.tokens.push(Token::eof(self.line));
+        if !self.interpolations.is_empty() {
+            let e = LoxResult::error(self.line, "Unterminated string interpolation.")
+                .in_module(&self.module);
+            e.report();
+            had_error = Some(e);
+        }
+        let mut eof = Token::eof(self.line);
+        eof.module = self.module.clone();
+        self.tokens.push(eof);
         if let Some(e) = had_error {
             Err(e)
         } else {
@@ -51,14 +83,117 @@ impl Scanner {
         match c {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some((depth, _)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace);
+            }
+            '}' => {
+                if let Some(&(0, kind)) = self.interpolations.last() {
+                    // end of an interpolated expression, the string goes on from here.
+                    self.interpolations.pop();
+                    self.add_token(TokenType::InterpolationEnd);
+                    self.start = self.current;
+                    self.string(kind)?;
+                } else {
+                    if let Some((depth, _)) = self.interpolations.last_mut() {
+                        *depth -= 1;
+                    }
+                    self.add_token(TokenType::RightBrace);
+                }
+            }
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
-            '+' => self.add_token(TokenType::Plus),
+            '.' => {
+                if self.is_match('.') && self.peek_next() == Some('.') {
+                    self.advance();
+                    self.advance();
+                    self.add_token(TokenType::DotDotDot);
+                } else if self.is_match('.') && self.peek_next() == Some('=') {
+                    self.advance();
+                    self.advance();
+                    self.add_token(TokenType::DotDotEqual);
+                } else if self.is_match('.') {
+                    self.advance();
+                    self.add_token(TokenType::DotDot);
+                } else {
+                    self.add_token(TokenType::Dot);
+                }
+            }
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
+            '-' => {
+                let tok = if self.is_match('-') {
+                    self.advance();
+                    TokenType::MinusMinus
+                } else if self.is_match('=') {
+                    self.advance();
+                    TokenType::MinusAssign
+                } else {
+                    TokenType::Minus
+                };
+                self.add_token(tok);
+            }
+            '+' => {
+                let tok = if self.is_match('+') {
+                    self.advance();
+                    TokenType::PlusPlus
+                } else if self.is_match('=') {
+                    self.advance();
+                    TokenType::PlusAssign
+                } else {
+                    TokenType::Plus
+                };
+                self.add_token(tok);
+            }
             ';' => self.add_token(TokenType::SemiColon),
-            '*' => self.add_token(TokenType::Star),
+            '*' => {
+                let tok = if self.is_match('*') {
+                    self.advance();
+                    TokenType::StarStar
+                } else if self.is_match('=') {
+                    self.advance();
+                    TokenType::StarAssign
+                } else {
+                    TokenType::Star
+                };
+                self.add_token(tok);
+            }
+            // floor division, `//` already starts a comment.
+            '~' => {
+                let tok = if self.is_match('/') {
+                    self.advance();
+                    TokenType::TildeSlash
+                } else {
+                    TokenType::Tilde
+                };
+                self.add_token(tok);
+            }
+            '&' => self.add_token(TokenType::Ampersand),
+            '|' => self.add_token(TokenType::Pipe),
+            '^' => self.add_token(TokenType::Caret),
+            '%' => {
+                let tok = if self.is_match('=') {
+                    self.advance();
+                    TokenType::PercentAssign
+                } else {
+                    TokenType::Percent
+                };
+                self.add_token(tok);
+            }
+            ':' => self.add_token(TokenType::Colon),
+            '?' => {
+                let tok = if self.is_match('?') {
+                    self.advance();
+                    TokenType::QuestionQuestion
+                } else if self.is_match('.') {
+                    self.advance();
+                    TokenType::QuestionDot
+                } else {
+                    TokenType::Question
+                };
+                self.add_token(tok);
+            }
             '!' => {
                 let tok = if self.is_match('=') {
                     self.advance();
@@ -72,6 +207,9 @@ impl Scanner {
                 let tok = if self.is_match('=') {
                     self.advance();
                     TokenType::Equal
+                } else if self.is_match('>') {
+                    self.advance();
+                    TokenType::Arrow
                 } else {
                     TokenType::Assign
                 };
@@ -81,6 +219,9 @@ impl Scanner {
                 let tok = if self.is_match('=') {
                     self.advance();
                     TokenType::GreaterEqual
+                } else if self.is_match('>') {
+                    self.advance();
+                    TokenType::GreaterGreater
                 } else {
                     TokenType::Greater
                 };
@@ -90,6 +231,9 @@ impl Scanner {
                 let tok = if self.is_match('=') {
                     self.advance();
                     TokenType::LessEqual
+                } else if self.is_match('<') {
+                    self.advance();
+                    TokenType::LessLess
                 } else {
                     TokenType::Less
                 };
@@ -112,6 +256,9 @@ impl Scanner {
                     let start_line = self.line;
                     // self.advance();
                     self.scan_comment(start, start_line)?;
+                } else if self.is_match('=') {
+                    self.advance();
+                    self.add_token(TokenType::SlashAssign);
                 } else {
                     self.add_token(TokenType::Slash);
                 }
@@ -121,13 +268,19 @@ impl Scanner {
                 self.line += 1;
             }
             '"' => {
-                self.string()?;
+                self.start_string(false)?;
             }
             '0'..='9' => {
-                self.number();
+                self.number()?;
+            }
+            '#' => {
+                self.private_name()?;
             }
             _ => {
-                if c.is_alphanumeric() || c == '_' {
+                if c == 'r' && self.peek() == Some('"') {
+                    self.advance();
+                    self.start_string(true)?;
+                } else if c.is_alphanumeric() || c == '_' {
                     self.identifier();
                 } else {
                     return Err(LoxResult::error(self.line, "Unknown token type"));
@@ -178,21 +331,123 @@ impl Scanner {
         }
     }
 
-    fn number(&mut self) {
-        while Scanner::is_digit(self.peek()) {
+    // `#name`, a private member of a class. The lexeme keeps the '#' so private names never
+    // clash with public ones.
+    fn private_name(&mut self) -> Result<(), LoxResult> {
+        if !self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        {
+            return Err(LoxResult::error(self.line, "Expect a name after '#'."));
+        }
+        while Scanner::is_alpha_numeric(self.peek()) {
             self.advance();
         }
+        self.add_token(TokenType::PrivateName);
+        Ok(())
+    }
+
+    // literals without a fraction or exponent are integers, the others are floats.
+    // 0x, 0b and 0o prefixes give hexadecimal, binary and octal integers, and `_` may be
+    // used between digits as a separator.
+    fn number(&mut self) -> Result<(), LoxResult> {
+        if self.source[self.start] == '0' {
+            let radix = match self.peek() {
+                Some('x') | Some('X') => Some((16, "hexadecimal")),
+                Some('b') | Some('B') => Some((2, "binary")),
+                Some('o') | Some('O') => Some((8, "octal")),
+                _ => None,
+            };
+            if let Some((radix, name)) = radix {
+                self.advance();
+                let digits = self.digits(radix, String::new())?;
+                if digits.is_empty() {
+                    return Err(LoxResult::error(
+                        self.line,
+                        &format!(
+                            "Expect digits after '{}'.",
+                            self.source[self.start..self.current]
+                                .iter()
+                                .collect::<String>()
+                        ),
+                    ));
+                }
+                if let Some(ch) = self.peek().filter(|ch| ch.is_ascii_alphanumeric()) {
+                    return Err(LoxResult::error(
+                        self.line,
+                        &format!("Invalid digit '{}' in {} literal.", ch, name),
+                    ));
+                }
+                let num = i64::from_str_radix(&digits, radix)
+                    .map_err(|_| LoxResult::error(self.line, "Integer literal is too large."))?;
+                self.add_token_object(TokenType::Number, Some(Object::Int(num)));
+                return Ok(());
+            }
+        }
+
+        let mut val = self.digits(10, self.source[self.start].to_string())?;
+        let mut is_float = false;
 
         if Some('.') == self.peek() && Scanner::is_digit(self.peek_next()) {
             self.advance();
-            while Scanner::is_digit(self.peek()) {
+            val.push('.');
+            val = self.digits(10, val)?;
+            is_float = true;
+        }
+
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            self.advance();
+            val.push('e');
+            if let Some(sign) = self.peek().filter(|ch| *ch == '+' || *ch == '-') {
                 self.advance();
+                val.push(sign);
             }
+            if !Scanner::is_digit(self.peek()) {
+                return Err(LoxResult::error(self.line, "Expect digits in exponent."));
+            }
+            val = self.digits(10, val)?;
+            is_float = true;
         }
-        let val: String = self.source[self.start..self.current].iter().collect();
-        let num: f64 = val.parse().unwrap();
-        self.add_token_object(TokenType::Number, Some(Object::Num(num)));
-        // Ok(())
+
+        if is_float {
+            let num: f64 = val.parse().unwrap();
+            self.add_token_object(TokenType::Number, Some(Object::Num(num)));
+        } else {
+            let num: i64 = val
+                .parse()
+                .map_err(|_| LoxResult::error(self.line, "Integer literal is too large."))?;
+            self.add_token_object(TokenType::Number, Some(Object::Int(num)));
+        }
+        Ok(())
+    }
+
+    // appends a run of digits to `val`, dropping the `_` separators, which are only allowed
+    // between two digits.
+    fn digits(&mut self, radix: u32, mut val: String) -> Result<String, LoxResult> {
+        let mut previous = val.chars().last();
+        while let Some(ch) = self.peek() {
+            if ch == '_' {
+                if !previous.is_some_and(|p| p.is_digit(radix)) {
+                    return Err(LoxResult::error(
+                        self.line,
+                        "Digit separator '_' must be between digits.",
+                    ));
+                }
+            } else if ch.is_digit(radix) {
+                val.push(ch);
+            } else {
+                break;
+            }
+            previous = Some(ch);
+            self.advance();
+        }
+        if previous == Some('_') {
+            return Err(LoxResult::error(
+                self.line,
+                "Digit separator '_' must be between digits.",
+            ));
+        }
+        Ok(val)
     }
 
     fn is_digit(ch: Option<char>) -> bool {
@@ -205,33 +460,192 @@ impl Scanner {
 
     fn is_alpha_numeric(ch: Option<char>) -> bool {
         if let Some(ch) = ch {
-            ch.is_ascii_alphanumeric()
+            ch.is_ascii_alphanumeric() || ch == '_'
         } else {
             false
         }
     }
 
-    fn string(&mut self) -> Result<(), LoxResult> {
-        while let Some(ch) = self.peek() {
-            if ch == '"' {
+    // called after the opening quote, `"""` opens a multi-line string whose common indentation
+    // is stripped, and a leading newline right after it is dropped.
+    fn start_string(&mut self, raw: bool) -> Result<(), LoxResult> {
+        let triple = self.peek() == Some('"') && self.peek_next() == Some('"');
+        let mut kind = StringKind {
+            raw,
+            triple,
+            indent: 0,
+        };
+        if triple {
+            self.advance();
+            self.advance();
+            kind.indent = self.triple_quote_indent(raw);
+            if self.peek() == Some('\n') {
+                self.advance();
+                self.line += 1;
+                self.skip_indent(kind.indent);
+            }
+        }
+        self.string(kind)
+    }
+
+    // a string containing `${expr}` is split into Interpolation tokens holding the text before
+    // each expression, followed by the expression's tokens and an InterpolationEnd for its
+    // closing brace, and a final String token.
+    fn string(&mut self, kind: StringKind) -> Result<(), LoxResult> {
+        let mut value = String::new();
+        let mut error = None;
+        loop {
+            let ch = match self.peek() {
+                Some(ch) => ch,
+                // a string inside an interpolation that never closes is reported as the
+                // unterminated interpolation alone.
+                None if !self.interpolations.is_empty() => return Ok(()),
+                None => return Err(LoxResult::error(self.line, "Unterminated string.")),
+            };
+            if ch == '"' && (!kind.triple || self.is_triple_quote()) {
+                self.advance();
+                if kind.triple {
+                    self.advance();
+                    self.advance();
+                }
                 break;
             }
+            if !kind.raw && ch == '$' && self.peek_next() == Some('{') {
+                self.advance();
+                self.advance();
+                self.add_token_object(TokenType::Interpolation, Some(Object::Str(value)));
+                self.interpolations.push((0, kind));
+                return error.map_or(Ok(()), Err);
+            }
+
+            self.advance();
             if ch == '\n' {
                 self.line += 1;
+                if kind.triple {
+                    // the line holding the closing quotes only indents them.
+                    if self.only_whitespace_before_closing() {
+                        self.skip_indent(usize::MAX);
+                        continue;
+                    }
+                    value.push(ch);
+                    self.skip_indent(kind.indent);
+                    continue;
+                }
+            }
+
+            if !kind.raw && ch == '\\' {
+                match self.escape() {
+                    Ok(escaped) => value.push(escaped),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                }
+            } else {
+                value.push(ch);
             }
-            self.advance();
         }
-        if self.is_at_end() {
-            return Err(LoxResult::error(self.line, "Unterminated string."));
+
+        if let Some(e) = error {
+            return Err(e);
         }
+        self.add_token_object(TokenType::String, Some(Object::Str(value)));
+        Ok(())
+    }
 
+    fn escape(&mut self) -> Result<char, LoxResult> {
+        let ch = match self.peek() {
+            Some(ch) => ch,
+            None => return Err(LoxResult::error(self.line, "Unterminated string.")),
+        };
         self.advance();
+        match ch {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            '$' => Ok('$'),
+            'u' => {
+                if self.peek() != Some('{') {
+                    return Err(LoxResult::error(
+                        self.line,
+                        "Expect '{' after '\\u' in escape sequence.",
+                    ));
+                }
+                self.advance();
+                let mut digits = String::new();
+                while let Some(ch) = self.peek() {
+                    if ch == '}' || ch == '"' || digits.len() > 6 {
+                        break;
+                    }
+                    digits.push(self.advance());
+                }
+                if self.peek() != Some('}') {
+                    return Err(LoxResult::error(
+                        self.line,
+                        "Unterminated unicode escape sequence.",
+                    ));
+                }
+                self.advance();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| !digits.is_empty() && digits.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        LoxResult::error(
+                            self.line,
+                            &format!("Invalid unicode escape sequence '\\u{{{}}}'.", digits),
+                        )
+                    })
+            }
+            _ => Err(LoxResult::error(
+                self.line,
+                &format!("Invalid escape sequence '\\{}'.", ch),
+            )),
+        }
+    }
 
-        let val: String = self.source[self.start + 1..self.current - 1]
+    fn is_triple_quote(&self) -> bool {
+        self.source[self.current..].starts_with(&['"', '"', '"'])
+    }
+
+    fn only_whitespace_before_closing(&self) -> bool {
+        let rest = &self.source[self.current..];
+        let end = rest
+            .iter()
+            .position(|ch| *ch != ' ' && *ch != '\t')
+            .unwrap_or(rest.len());
+        rest[end..].starts_with(&['"', '"', '"'])
+    }
+
+    fn skip_indent(&mut self, indent: usize) {
+        let mut skipped = 0;
+        while skipped < indent && matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.advance();
+            skipped += 1;
+        }
+    }
+
+    // the smallest indentation of the non-blank lines following the opening quotes.
+    fn triple_quote_indent(&self, raw: bool) -> usize {
+        let mut end = self.current;
+        while end < self.source.len() && !self.source[end..].starts_with(&['"', '"', '"']) {
+            if !raw && self.source[end] == '\\' {
+                end += 1;
+            }
+            end += 1;
+        }
+        let content: String = self.source[self.current..end.min(self.source.len())]
             .iter()
             .collect();
-        self.add_token_object(TokenType::String, Some(Object::Str(val)));
-        Ok(())
+        content
+            .split('\n')
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+            .min()
+            .unwrap_or(0)
     }
 
     fn advance(&mut self) -> char {
@@ -246,8 +660,9 @@ impl Scanner {
 
     fn add_token_object(&mut self, ttype: TokenType, literal: Option<Object>) {
         let lexeme = self.source[self.start..self.current].iter().collect();
-        self.tokens
-            .push(Token::new(ttype, lexeme, literal, self.line))
+        let mut token = Token::new(ttype, lexeme, literal, self.line);
+        token.module = self.module.clone();
+        self.tokens.push(token)
     }
 
     fn is_match(&mut self, expected: char) -> bool {
@@ -272,21 +687,33 @@ impl Scanner {
         match check {
             "and" => Some(TokenType::And),
             "break" => Some(TokenType::Break),
+            "case" => Some(TokenType::Case),
             "class" => Some(TokenType::Class),
+            "const" => Some(TokenType::Const),
+            "continue" => Some(TokenType::Continue),
             "else" => Some(TokenType::Else),
+            "enum" => Some(TokenType::Enum),
+            "export" => Some(TokenType::Export),
             "false" => Some(TokenType::False),
             "for" => Some(TokenType::For),
             "fun" => Some(TokenType::Fun),
             "if" => Some(TokenType::If),
+            "import" => Some(TokenType::Import),
+            "in" => Some(TokenType::In),
+            "is" => Some(TokenType::Is),
+            "match" => Some(TokenType::Match),
             "nil" => Some(TokenType::Nil),
             "or" => Some(TokenType::Or),
             "print" => Some(TokenType::Print),
             "return" => Some(TokenType::Return),
             "super" => Some(TokenType::Super),
             "this" => Some(TokenType::This),
+            "trait" => Some(TokenType::Trait),
             "true" => Some(TokenType::True),
             "var" => Some(TokenType::Var),
             "while" => Some(TokenType::While),
+            "with" => Some(TokenType::With),
+            "yield" => Some(TokenType::Yield),
             _ => None,
         }
     }
@@ -2,6 +2,7 @@ use crate::object::Object;
 use crate::token_type::*;
 use std::fmt;
 use std::fmt::Formatter;
+use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
@@ -9,6 +10,8 @@ pub struct Token {
     pub lexeme: String,
     pub literal: Option<Object>,
     pub line: usize,
+    // the path of the imported module the token was scanned from, None in the main script.
+    pub module: Option<Rc<str>>,
 }
 
 impl Token {
@@ -18,6 +21,7 @@ impl Token {
             lexeme,
             literal,
             line,
+            module: None,
         }
     }
     pub fn is(&self, ttype: TokenType) -> bool {
@@ -38,6 +42,18 @@ impl Token {
             lexeme: self.lexeme.clone(),
             literal: self.literal.clone(),
             line: self.line,
+            module: self.module.clone(),
+        }
+    }
+
+    /// a token the interpreter makes up in place of this one, reported at the same place.
+    pub fn stand_in(&self, ttype: TokenType, lexeme: &str) -> Token {
+        Token {
+            ttype,
+            lexeme: lexeme.to_string(),
+            literal: None,
+            line: self.line,
+            module: self.module.clone(),
         }
     }
 
@@ -47,6 +63,7 @@ impl Token {
             lexeme: "".to_string(),
             literal: None,
             line,
+            module: None,
         }
     }
 }
@@ -5,39 +5,80 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
+    DotDot,
+    DotDotEqual,
+    DotDotDot,
+    Question,
+    QuestionDot,
+    QuestionQuestion,
+    Colon,
     Minus,
+    MinusAssign,
+    MinusMinus,
+    Percent,
+    PercentAssign,
     Plus,
+    PlusAssign,
+    PlusPlus,
     SemiColon,
     Slash,
+    SlashAssign,
     Star,
+    StarAssign,
+    StarStar,
+    TildeSlash,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
     Bang,
     BangEqual,
     Assign,
+    Arrow,
     Equal,
     Greater,
     GreaterEqual,
+    GreaterGreater,
     Less,
     LessEqual,
+    LessLess,
     Identifier,
+    PrivateName,
     String,
+    Interpolation,
+    InterpolationEnd,
     Number,
     And,
+    Case,
     Class,
+    Const,
+    Continue,
     Else,
+    Enum,
+    Export,
     False,
     Fun,
     For,
     If,
+    Import,
+    In,
+    Is,
+    Match,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Trait,
     True,
     Var,
     While,
+    With,
+    Yield,
     Eof,
 }
@@ -0,0 +1,107 @@
+//! Runs every script under `tests/lox` and checks its output against the comments in it:
+//!
+//! - `// expect: value` is a line the script prints.
+//! - `// expect runtime error: message` is a runtime error reported at the comment's line.
+//! - `// Error at 'x': message` is a compile error reported at the comment's line.
+//! - `// [line N] message` is an error reported at another line.
+//! - `// [path, line N] message` is an error reported in an imported module.
+//!
+//! Scripts inside `lib` directories are only imported by other scripts and aren't run. The
+//! scanner echoes every comment it reads, those lines are left out of the output.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Default)]
+struct Expected {
+    output: Vec<String>,
+    errors: Vec<String>,
+}
+
+fn expectations(source: &str) -> Expected {
+    let mut expected = Expected::default();
+    for (i, line) in source.lines().enumerate() {
+        let Some((_, comment)) = line.split_once("// ") else {
+            continue;
+        };
+        let line = i + 1;
+        if let Some(value) = comment.strip_prefix("expect: ") {
+            expected.output.push(value.to_string());
+        } else if let Some(message) = comment.strip_prefix("expect runtime error: ") {
+            expected.errors.push(format!("[line {}] {}", line, message));
+        } else if comment.starts_with("Error") {
+            expected.errors.push(format!("[line {}] {}", line, comment));
+        } else if comment.starts_with("[line ")
+            || (comment.starts_with('[') && comment.contains(", line "))
+        {
+            expected.errors.push(comment.to_string());
+        }
+    }
+    expected
+}
+
+fn collect_scripts(dir: &Path, scripts: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            if !path.ends_with("lib") {
+                collect_scripts(&path, scripts);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            scripts.push(path);
+        }
+    }
+}
+
+fn lines(bytes: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn run(script: &Path) -> Result<(), String> {
+    let expected = expectations(&fs::read_to_string(script).unwrap());
+    let result = Command::new(env!("CARGO_BIN_EXE_rs-lox"))
+        .arg(script)
+        .output()
+        .unwrap();
+    let output = lines(&result.stdout)
+        .into_iter()
+        .filter(|line| !(line.starts_with("[line: ") && line.contains("] Comment: ")))
+        .collect::<Vec<String>>();
+    let errors = lines(&result.stderr);
+    if output == expected.output && errors == expected.errors {
+        return Ok(());
+    }
+    Err(format!(
+        "{}\n  expected output: {:?}\n  actual output:   {:?}\n  expected errors: {:?}\n  actual errors:   {:?}",
+        script.display(),
+        expected.output,
+        output,
+        expected.errors,
+        errors
+    ))
+}
+
+#[test]
+fn scripts() {
+    let mut scripts = Vec::new();
+    collect_scripts(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox"),
+        &mut scripts,
+    );
+    scripts.sort();
+    let failures = scripts
+        .iter()
+        .filter_map(|script| run(script).err())
+        .collect::<Vec<String>>();
+    assert!(
+        failures.is_empty(),
+        "{} of {} scripts failed:\n\n{}",
+        failures.len(),
+        scripts.len(),
+        failures.join("\n\n")
+    );
+}